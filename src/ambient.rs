@@ -0,0 +1,169 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::cube::Cube;
+use crate::noise::hash_vec3;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+const AO_BIAS: f32 = 1e-3;
+// Distancia a la que se buscan los bloques vecinos desde cada vértice
+const NEIGHBOUR_PROBE: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AoMode {
+    Off,
+    SmoothLighting,
+    RayTraced,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientOcclusion {
+    pub level: f32,
+    pub mode: AoMode,
+    pub radius: f32,
+    pub samples: u32,
+}
+
+impl AmbientOcclusion {
+    pub fn new(level: f32, mode: AoMode, radius: f32, samples: u32) -> Self {
+        AmbientOcclusion {
+            level,
+            mode,
+            radius,
+            samples,
+        }
+    }
+
+    // Cambiar al siguiente modo: apagado -> suavizado por vértice -> trazado de rayos
+    pub fn next_mode(&mut self) {
+        self.mode = match self.mode {
+            AoMode::Off => AoMode::SmoothLighting,
+            AoMode::SmoothLighting => AoMode::RayTraced,
+            AoMode::RayTraced => AoMode::Off,
+        };
+    }
+
+    // Factor de visibilidad ambiental en [0, 1] (1 = sin oclusión)
+    pub fn occlusion(&self, intersect: &Intersect, cube: &Cube, objects: &[Cube]) -> f32 {
+        match self.mode {
+            AoMode::Off => 1.0,
+            AoMode::SmoothLighting => smooth_lighting(intersect, cube, objects),
+            AoMode::RayTraced => ray_traced(intersect, objects, self.radius, self.samples),
+        }
+    }
+}
+
+fn is_solid(point: &Vec3, objects: &[Cube]) -> bool {
+    objects.iter().any(|cube| cube.material.casts_shadow() && cube.contains(point))
+}
+
+// Ejes tangentes de una cara alineada a los ejes, a partir de su normal
+fn face_axes(normal: &Vec3) -> (usize, usize, usize) {
+    if normal.x.abs() > 0.5 {
+        (0, 1, 2)
+    } else if normal.y.abs() > 0.5 {
+        (1, 0, 2)
+    } else {
+        (2, 0, 1)
+    }
+}
+
+// Oclusión estilo Minecraft para un vértice según sus dos lados y la esquina
+fn vertex_ao(side1: bool, side2: bool, corner: bool) -> f32 {
+    if side1 && side2 {
+        return 0.0;
+    }
+    (3 - side1 as u8 - side2 as u8 - corner as u8) as f32 / 3.0
+}
+
+fn smooth_lighting(intersect: &Intersect, cube: &Cube, objects: &[Cube]) -> f32 {
+    let (_, t1, t2) = face_axes(&intersect.normal);
+    let normal_offset = intersect.normal * NEIGHBOUR_PROBE;
+
+    // Valor de AO en cada una de las cuatro esquinas de la cara
+    let mut corners = [[0.0; 2]; 2];
+    for (i, row) in corners.iter_mut().enumerate() {
+        for (j, corner_ao) in row.iter_mut().enumerate() {
+            let mut vertex = intersect.point;
+            vertex[t1] = if i == 0 { cube.min[t1] } else { cube.max[t1] };
+            vertex[t2] = if j == 0 { cube.min[t2] } else { cube.max[t2] };
+
+            let s1 = if i == 0 { -1.0 } else { 1.0 };
+            let s2 = if j == 0 { -1.0 } else { 1.0 };
+
+            let mut side1 = vertex + normal_offset;
+            side1[t1] += s1 * NEIGHBOUR_PROBE;
+            side1[t2] -= s2 * NEIGHBOUR_PROBE;
+
+            let mut side2 = vertex + normal_offset;
+            side2[t1] -= s1 * NEIGHBOUR_PROBE;
+            side2[t2] += s2 * NEIGHBOUR_PROBE;
+
+            let mut corner = vertex + normal_offset;
+            corner[t1] += s1 * NEIGHBOUR_PROBE;
+            corner[t2] += s2 * NEIGHBOUR_PROBE;
+
+            *corner_ao = vertex_ao(
+                is_solid(&side1, objects),
+                is_solid(&side2, objects),
+                is_solid(&corner, objects),
+            );
+        }
+    }
+
+    // Interpolación bilineal entre las esquinas según la posición en la cara
+    let u = ((intersect.point[t1] - cube.min[t1]) / (cube.max[t1] - cube.min[t1])).clamp(0.0, 1.0);
+    let v = ((intersect.point[t2] - cube.min[t2]) / (cube.max[t2] - cube.min[t2])).clamp(0.0, 1.0);
+
+    let bottom = corners[0][0] * (1.0 - u) + corners[1][0] * u;
+    let top = corners[0][1] * (1.0 - u) + corners[1][1] * u;
+    bottom * (1.0 - v) + top * v
+}
+
+// Base ortonormal alrededor de la normal para muestrear el hemisferio
+fn tangent_basis(normal: &Vec3) -> (Vec3, Vec3) {
+    let helper = if normal.x.abs() > 0.9 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+    (tangent, bitangent)
+}
+
+fn ray_traced(intersect: &Intersect, objects: &[Cube], radius: f32, samples: u32) -> f32 {
+    if samples == 0 {
+        return 1.0;
+    }
+
+    let normal = intersect.normal;
+    let (tangent, bitangent) = tangent_basis(&normal);
+    let origin = intersect.point + normal * AO_BIAS;
+    // Rotación pseudoaleatoria por punto para que el patrón de muestras no forme bandas
+    let rotation = hash_vec3(&intersect.point);
+    let golden_ratio = 0.618_034;
+
+    let mut occluded = 0;
+    for i in 0..samples {
+        // Muestreo con peso coseno sobre el hemisferio (secuencia de Fibonacci)
+        let u1 = (i as f32 + 0.5) / samples as f32;
+        let u2 = (i as f32 * golden_ratio + rotation).fract();
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let direction = (tangent * (r * phi.cos())
+            + bitangent * (r * phi.sin())
+            + normal * (1.0 - u1).sqrt())
+        .normalize();
+
+        let blocked = objects.iter().filter(|object| object.material.casts_shadow()).any(|object| {
+            let hit = object.ray_intersect(&origin, &direction);
+            hit.is_intersecting && hit.distance < radius
+        });
+        if blocked {
+            occluded += 1;
+        }
+    }
+
+    1.0 - occluded as f32 / samples as f32
+}
@@ -0,0 +1,318 @@
+use exr::prelude::{AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes, SmallVec, WritableImage};
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::noise::hash3;
+
+// Pases de salida que se pueden ver en la ventana; `Beauty` es la imagen final
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AovKind {
+    Beauty,
+    Depth,
+    Normal,
+    Albedo,
+    MaterialId,
+    BlockId,
+    Shadow,
+}
+
+// Pases auxiliares en el orden en que se exportan
+pub const AOV_PASSES: [AovKind; 6] = [
+    AovKind::Depth,
+    AovKind::Normal,
+    AovKind::Albedo,
+    AovKind::MaterialId,
+    AovKind::BlockId,
+    AovKind::Shadow,
+];
+
+impl AovKind {
+    pub fn next(self) -> Self {
+        match self {
+            AovKind::Beauty => AovKind::Depth,
+            AovKind::Depth => AovKind::Normal,
+            AovKind::Normal => AovKind::Albedo,
+            AovKind::Albedo => AovKind::MaterialId,
+            AovKind::MaterialId => AovKind::BlockId,
+            AovKind::BlockId => AovKind::Shadow,
+            AovKind::Shadow => AovKind::Beauty,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AovKind::Beauty => "beauty",
+            AovKind::Depth => "depth",
+            AovKind::Normal => "normal",
+            AovKind::Albedo => "albedo",
+            AovKind::MaterialId => "material_id",
+            AovKind::BlockId => "block_id",
+            AovKind::Shadow => "shadow",
+        }
+    }
+}
+
+// Datos de la primera superficie que ve un píxel
+#[derive(Debug, Clone, Copy)]
+pub struct AovSample {
+    // Color promediado del píxel en punto flotante, antes de cuantizarlo a 8 bits
+    pub radiance: [f32; 3],
+    // Profundidad lineal a lo largo del eje de la cámara (infinita si el rayo no choca)
+    pub depth: f32,
+    pub normal: Vec3,
+    pub albedo: Color,
+    // Los identificadores empiezan en 1; 0 significa cielo
+    pub material_id: u32,
+    pub block_id: u32,
+    // 0 con el punto totalmente iluminado, 1 totalmente a la sombra
+    pub shadow: f32,
+    // Rayos secundarios que trazó `cast_ray` para la muestra, para la vista de profundidad de reflexión
+    pub secondary_rays: u32,
+}
+
+impl AovSample {
+    pub fn empty() -> Self {
+        AovSample {
+            radiance: [0.0; 3],
+            depth: f32::INFINITY,
+            normal: Vec3::zeros(),
+            albedo: Color::new(0, 0, 0),
+            material_id: 0,
+            block_id: 0,
+            shadow: 0.0,
+            secondary_rays: 0,
+        }
+    }
+}
+
+// Promedio de los pases de todas las muestras de un píxel, como se promedia la radiancia. La
+// profundidad y la normal se promedian entre las muestras que chocan con algo; los identificadores
+// son los de la primera muestra, porque no se pueden promediar.
+pub struct AovAccumulator {
+    samples: u32,
+    hits: u32,
+    depth: f32,
+    normal: Vec3,
+    albedo: [f32; 3],
+    shadow: f32,
+    first: Option<AovSample>,
+}
+
+impl AovAccumulator {
+    pub fn new() -> Self {
+        AovAccumulator {
+            samples: 0,
+            hits: 0,
+            depth: 0.0,
+            normal: Vec3::zeros(),
+            albedo: [0.0; 3],
+            shadow: 0.0,
+            first: None,
+        }
+    }
+
+    pub fn add(&mut self, sample: &AovSample) {
+        self.samples += 1;
+        if sample.depth.is_finite() {
+            self.hits += 1;
+            self.depth += sample.depth;
+            self.normal += sample.normal;
+        }
+        for (total, channel) in self.albedo.iter_mut().zip(sample.albedo.to_f32().iter()) {
+            *total += channel;
+        }
+        self.shadow += sample.shadow;
+        self.first.get_or_insert(*sample);
+    }
+
+    // Escribir el promedio en `aov`, salvo la radiancia
+    pub fn finish(&self, aov: &mut AovSample) {
+        let Some(first) = self.first else {
+            return;
+        };
+        let samples = self.samples as f32;
+        aov.depth = if self.hits > 0 { self.depth / self.hits as f32 } else { f32::INFINITY };
+        aov.normal = if self.normal.magnitude() > 1e-6 { self.normal.normalize() } else { Vec3::zeros() };
+        aov.albedo = Color::from_f32(self.albedo[0] / samples, self.albedo[1] / samples, self.albedo[2] / samples);
+        aov.material_id = first.material_id;
+        aov.block_id = first.block_id;
+        aov.shadow = self.shadow / samples;
+    }
+}
+
+#[derive(Clone)]
+pub struct AovBuffers {
+    pub width: usize,
+    pub height: usize,
+    pub radiance: Vec<[f32; 3]>,
+    pub depth: Vec<f32>,
+    pub normal: Vec<Vec3>,
+    pub albedo: Vec<Color>,
+    pub material_id: Vec<u32>,
+    pub block_id: Vec<u32>,
+    pub shadow: Vec<f32>,
+}
+
+impl AovBuffers {
+    pub fn new(width: usize, height: usize) -> Self {
+        let size = width * height;
+        AovBuffers {
+            width,
+            height,
+            radiance: vec![[0.0; 3]; size],
+            depth: vec![f32::INFINITY; size],
+            normal: vec![Vec3::zeros(); size],
+            albedo: vec![Color::new(0, 0, 0); size],
+            material_id: vec![0; size],
+            block_id: vec![0; size],
+            shadow: vec![0.0; size],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, sample: &AovSample) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+            self.radiance[index] = sample.radiance;
+            self.depth[index] = sample.depth;
+            self.normal[index] = sample.normal;
+            self.albedo[index] = sample.albedo;
+            self.material_id[index] = sample.material_id;
+            self.block_id[index] = sample.block_id;
+            self.shadow[index] = sample.shadow;
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> AovSample {
+        let index = y * self.width + x;
+        AovSample {
+            radiance: self.radiance[index],
+            depth: self.depth[index],
+            normal: self.normal[index],
+            albedo: self.albedo[index],
+            material_id: self.material_id[index],
+            block_id: self.block_id[index],
+            shadow: self.shadow[index],
+            // Sólo se usa mientras se traza la muestra; los buffers no lo guardan
+            secondary_rays: 0,
+        }
+    }
+
+    // Dibujar un pase en el framebuffer como una imagen visible
+    pub fn visualize(&self, kind: AovKind, framebuffer: &mut Framebuffer) {
+        if kind == AovKind::Beauty {
+            return;
+        }
+
+        // La profundidad se normaliza con el punto más lejano de la imagen
+        let max_depth = self
+            .depth
+            .iter()
+            .copied()
+            .filter(|depth| depth.is_finite())
+            .fold(0.0, f32::max)
+            .max(1e-4);
+
+        for y in 0..self.height.min(framebuffer.height) {
+            for x in 0..self.width.min(framebuffer.width) {
+                let index = y * self.width + x;
+                let color = match kind {
+                    AovKind::Beauty => return,
+                    AovKind::Depth => {
+                        let depth = self.depth[index];
+                        let value = if depth.is_finite() { 1.0 - depth / max_depth } else { 0.0 };
+                        Color::from_f32(value, value, value)
+                    }
+                    AovKind::Normal => {
+                        let normal = self.normal[index];
+                        if normal.magnitude_squared() > 0.0 {
+                            Color::from_f32(normal.x * 0.5 + 0.5, normal.y * 0.5 + 0.5, normal.z * 0.5 + 0.5)
+                        } else {
+                            Color::new(0, 0, 0)
+                        }
+                    }
+                    AovKind::Albedo => self.albedo[index],
+                    AovKind::MaterialId => id_color(self.material_id[index]),
+                    AovKind::BlockId => id_color(self.block_id[index]),
+                    AovKind::Shadow => {
+                        let value = self.shadow[index];
+                        Color::from_f32(value, value, value)
+                    }
+                };
+                framebuffer.draw_pixel(x, y, color.to_hex());
+            }
+        }
+    }
+
+    pub fn save(&self, kind: AovKind, file_path: &str) -> image::ImageResult<()> {
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+        self.visualize(kind, &mut framebuffer);
+        framebuffer.save(file_path)
+    }
+
+    // Guardar todos los pases como <carpeta>/<pase>.png
+    pub fn save_all(&self, output_dir: &str) -> image::ImageResult<()> {
+        std::fs::create_dir_all(output_dir)?;
+        for kind in AOV_PASSES {
+            self.save(kind, &format!("{}/{}.png", output_dir, kind.name()))?;
+        }
+        Ok(())
+    }
+
+    // Guardar la imagen en punto flotante como Radiance .hdr
+    pub fn save_hdr(&self, file_path: &str) -> image::ImageResult<()> {
+        let mut image = image::Rgb32FImage::new(self.width as u32, self.height as u32);
+        for (pixel, radiance) in image.pixels_mut().zip(self.radiance.iter()) {
+            *pixel = image::Rgb(*radiance);
+        }
+        image.save(file_path)
+    }
+
+    // Guardar un OpenEXR con la imagen en R, G, B y cada pase como una capa aparte
+    // (depth.Z, normal.X, albedo.R, material_id.id, ...) en una sola parte del archivo
+    pub fn save_exr(&self, file_path: &str) -> exr::error::UnitResult {
+        let f32_channel = |name: &str, values: Vec<f32>| AnyChannel::new(name, FlatSamples::F32(values));
+        let u32_channel = |name: &str, values: &[u32]| AnyChannel::new(name, FlatSamples::U32(values.to_vec()));
+        let rgb = |prefix: &str, pixels: &[[f32; 3]]| {
+            ["R", "G", "B"]
+                .into_iter()
+                .enumerate()
+                .map(|(channel, name)| {
+                    f32_channel(&format!("{}{}", prefix, name), pixels.iter().map(|pixel| pixel[channel]).collect())
+                })
+                .collect::<Vec<_>>()
+        };
+        let albedo: Vec<[f32; 3]> = self.albedo.iter().map(|color| color.to_linear()).collect();
+
+        let mut channels = rgb("", &self.radiance);
+        channels.extend(rgb("albedo.", &albedo));
+        channels.push(f32_channel("depth.Z", self.depth.clone()));
+        channels.push(f32_channel("normal.X", self.normal.iter().map(|normal| normal.x).collect()));
+        channels.push(f32_channel("normal.Y", self.normal.iter().map(|normal| normal.y).collect()));
+        channels.push(f32_channel("normal.Z", self.normal.iter().map(|normal| normal.z).collect()));
+        channels.push(u32_channel("material_id.id", &self.material_id));
+        channels.push(u32_channel("block_id.id", &self.block_id));
+        channels.push(f32_channel("shadow.Y", self.shadow.clone()));
+
+        let layer = Layer::new(
+            (self.width, self.height),
+            LayerAttributes::default(),
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(SmallVec::from_vec(channels)),
+        );
+        Image::from_layer(layer).write().to_file(file_path)
+    }
+}
+
+// Color arbitrario pero estable para cada identificador
+fn id_color(id: u32) -> Color {
+    if id == 0 {
+        return Color::new(0, 0, 0);
+    }
+    let id = id as i32;
+    Color::from_f32(
+        0.2 + 0.8 * hash3(id, 1, 0),
+        0.2 + 0.8 * hash3(id, 2, 0),
+        0.2 + 0.8 * hash3(id, 3, 0),
+    )
+}
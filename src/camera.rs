@@ -2,10 +2,78 @@
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+// Límites del campo de visión vertical y del tamaño de la vista ortográfica
+const MIN_FOV: f32 = PI / 18.0;
+const MAX_FOV: f32 = 2.0 * PI / 3.0;
+const MIN_ORTHO_HEIGHT: f32 = 2.0;
+const MAX_ORTHO_HEIGHT: f32 = 60.0;
+// Media altura de la vista isométrica, suficiente para encuadrar todo el diorama
+const ISOMETRIC_HEIGHT: f32 = 14.0;
+const ISOMETRIC_MIN_DISTANCE: f32 = 40.0;
+// Caras de un cube map (px, nx, py, ny, pz, nz): nombre, dirección de la vista y vector arriba.
+// Cada cara se ve tal como la vería el ojo, con +Y arriba en las caras laterales.
+pub const CUBE_FACES: [(&str, Vec3, Vec3); 6] = [
+    ("px", Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+    ("nx", Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+    ("py", Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+    ("ny", Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    ("pz", Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0)),
+    ("nz", Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0)),
+];
+// Radio de la lente al activar la profundidad de campo
+const DEFAULT_APERTURE: f32 = 0.6;
+const MIN_FOCUS_DISTANCE: f32 = 1.0;
+// Fracción del cuadro que el obturador queda abierto al activar el desenfoque de movimiento
+const DEFAULT_SHUTTER: f32 = 0.5;
+
+// Controlador que mueve la cámara: órbita alrededor del centro o vuelo libre
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    Orbit,
+    FreeFly,
+}
+
+impl CameraMode {
+    pub fn toggle(self) -> Self {
+        match self {
+            CameraMode::Orbit => CameraMode::FreeFly,
+            CameraMode::FreeFly => CameraMode::Orbit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+    // Panorama de 360° × 180°: la pantalla recorre longitud y latitud alrededor del ojo
+    Equirectangular,
+}
+
+#[derive(Clone)]
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
-    pub up: Vec3
+    pub up: Vec3,
+    pub projection: Projection,
+    // Campo de visión vertical de la perspectiva, en radianes
+    pub fov: f32,
+    // Media altura en unidades del mundo de lo que ve la proyección ortográfica
+    pub ortho_height: f32,
+    // Lente delgada: radio de la apertura (0 = cámara estenopeica) y distancia al plano enfocado
+    pub aperture: f32,
+    pub focus_distance: f32,
+    // Enfocar en cada cuadro el objeto bajo el centro de la pantalla
+    pub autofocus: bool,
+    // Muestras de la lente por píxel cuando la apertura es mayor que 0
+    pub lens_samples: u32,
+    // Obturador: fracción del cuadro (al final de él) durante la que se expone la imagen, 0 = instantáneo
+    pub shutter: f32,
+    // Muestras de tiempo por píxel cuando el obturador está abierto
+    pub motion_samples: u32,
+    // Pose al comienzo del cuadro, desde la que se interpola el movimiento
+    pub previous_eye: Vec3,
+    pub previous_center: Vec3
 }
 
 impl Camera {
@@ -13,18 +81,162 @@ impl Camera {
         Camera {
             eye,
             center,
-            up
+            up,
+            projection: Projection::Perspective,
+            fov: PI / 3.0,
+            ortho_height: 12.0,
+            aperture: 0.0,
+            focus_distance: (center - eye).magnitude(),
+            autofocus: false,
+            lens_samples: 4,
+            shutter: 0.0,
+            motion_samples: 4,
+            previous_eye: eye,
+            previous_center: center
         }
     }
 
+    // Guardar la pose actual como inicio del siguiente cuadro
+    pub fn remember_pose(&mut self) {
+        self.previous_eye = self.eye;
+        self.previous_center = self.center;
+    }
+
+    // Instante del cuadro en [0, 1] para una muestra en [0, 1) del intervalo del obturador
+    pub fn shutter_time(&self, sample: f32) -> f32 {
+        1.0 - self.shutter * sample
+    }
+
+    // Cámara en el instante `time` del cuadro (0 = inicio, 1 = pose actual)
+    pub fn at_time(&self, time: f32) -> Camera {
+        let mut camera = self.clone();
+        camera.eye = self.previous_eye + (self.eye - self.previous_eye) * time;
+        camera.center = self.previous_center + (self.center - self.previous_center) * time;
+        camera
+    }
+
+    // Muestras por píxel: varias con profundidad de campo o desenfoque de movimiento
+    pub fn samples_per_pixel(&self) -> u32 {
+        let lens_samples = if self.aperture > 0.0 { self.lens_samples } else { 1 };
+        let motion_samples = if self.shutter > 0.0 { self.motion_samples } else { 1 };
+        lens_samples.max(motion_samples).max(1)
+    }
+
+    // Rayo (origen, dirección) que pasa por un punto de la pantalla en [-1, 1];
+    // `lens_sample` en [0, 1)² elige el punto de la lente cuando hay profundidad de campo
+    pub fn ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32, lens_sample: (f32, f32)) -> (Vec3, Vec3) {
+        let (pinhole_origin, direction) = self.pinhole_ray(screen_x, screen_y, aspect_ratio);
+        if self.aperture <= 0.0 || self.projection == Projection::Equirectangular {
+            return (pinhole_origin, direction);
+        }
+
+        // Todos los rayos del píxel convergen en el plano enfocado
+        let (right, up, forward) = self.basis();
+        let focus_point = pinhole_origin + direction * (self.focus_distance / direction.dot(&forward));
+        let (u, v) = concentric_disk(lens_sample);
+        let origin = pinhole_origin + right * (u * self.aperture) + up * (v * self.aperture);
+        (origin, (focus_point - origin).normalize())
+    }
+
+    fn pinhole_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32) -> (Vec3, Vec3) {
+        match self.projection {
+            Projection::Perspective => {
+                let perspective_scale = (self.fov * 0.5).tan();
+                let direction = Vec3::new(
+                    screen_x * aspect_ratio * perspective_scale,
+                    screen_y * perspective_scale,
+                    -1.0,
+                );
+                (self.eye, self.base_change(&direction.normalize()))
+            }
+            Projection::Orthographic => {
+                // Todos los rayos son paralelos y parten del plano que pasa por el ojo
+                let offset = self.base_change_unnormalized(&Vec3::new(
+                    screen_x * aspect_ratio * self.ortho_height,
+                    screen_y * self.ortho_height,
+                    0.0,
+                ));
+                let forward = (self.center - self.eye).normalize();
+                (self.eye + offset, forward)
+            }
+            Projection::Equirectangular => {
+                // El centro de la imagen mira hacia adelante; los bordes izquierdo y derecho, hacia atrás
+                let longitude = screen_x * PI;
+                let latitude = screen_y * PI / 2.0;
+                let direction = Vec3::new(
+                    longitude.sin() * latitude.cos(),
+                    latitude.sin(),
+                    -longitude.cos() * latitude.cos(),
+                );
+                (self.eye, self.base_change(&direction))
+            }
+        }
+    }
+
+    // Inversa de `pinhole_ray`: posición en pantalla ([-1, 1] en cada eje) y profundidad lineal de un punto.
+    // Devuelve None si el punto queda detrás de la cámara o con la proyección equirectangular.
+    pub fn project(&self, point: &Vec3, aspect_ratio: f32) -> Option<(f32, f32, f32)> {
+        let (right, up, forward) = self.basis();
+        let offset = point - self.eye;
+        let (x, y, depth) = (offset.dot(&right), offset.dot(&up), offset.dot(&forward));
+        match self.projection {
+            Projection::Perspective if depth > 1e-4 => {
+                let perspective_scale = (self.fov * 0.5).tan();
+                Some((x / (depth * aspect_ratio * perspective_scale), y / (depth * perspective_scale), depth))
+            }
+            Projection::Orthographic if depth > 0.0 => {
+                Some((x / (aspect_ratio * self.ortho_height), y / self.ortho_height, depth))
+            }
+            _ => None,
+        }
+    }
+
+    // Cámara nivelada en el mismo ojo para renderizar un panorama equirectangular
+    pub fn panorama(&self) -> Camera {
+        let forward = self.center - self.eye;
+        let horizontal = if forward.x.abs() + forward.z.abs() > 1e-4 {
+            Vec3::new(forward.x, 0.0, forward.z).normalize()
+        } else {
+            Vec3::new(0.0, 0.0, -1.0)
+        };
+
+        let mut camera = self.clone();
+        camera.center = self.eye + horizontal;
+        camera.up = Vec3::new(0.0, 1.0, 0.0);
+        camera.projection = Projection::Equirectangular;
+        camera.aperture = 0.0;
+        camera.shutter = 0.0;
+        camera
+    }
+
+    // Cámara de 90° en el mismo ojo que mira hacia una cara del cube map
+    pub fn cube_face(&self, forward: Vec3, up: Vec3) -> Camera {
+        let mut camera = self.clone();
+        camera.center = self.eye + forward;
+        camera.up = up;
+        camera.projection = Projection::Perspective;
+        camera.fov = PI / 2.0;
+        camera.aperture = 0.0;
+        camera.shutter = 0.0;
+        camera
+    }
+
     pub fn base_change(&self, vector: &Vec3) -> Vec3 {
+        self.base_change_unnormalized(vector).normalize()
+    }
+
+    fn base_change_unnormalized(&self, vector: &Vec3) -> Vec3 {
+        let (right, up, forward) = self.basis();
+
+        vector.x * right + vector.y * up - vector.z * forward
+    }
+
+    // Ejes (derecha, arriba, adelante) de la cámara
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
         let forward = (self.center - self.eye).normalize();
         let right = forward.cross(&self.up).normalize();
         let up = right.cross(&forward).normalize();
-
-        let rotated = vector.x * right + vector.y * up - vector.z * forward;
-
-        return rotated.normalize();
+        (right, up, forward)
     }
 
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
@@ -52,5 +264,95 @@ impl Camera {
         let direction = (self.center - self.eye).normalize();  // Dirección hacia el centro
         self.eye += direction * amount;  // Acercar o alejar en esa dirección
     }
-    
-}
\ No newline at end of file
+
+    // Acercar (factor < 1) o alejar la imagen sin mover la cámara: cambia el FOV o el tamaño ortográfico
+    pub fn zoom_fov(&mut self, factor: f32) {
+        match self.projection {
+            Projection::Perspective => self.fov = (self.fov * factor).clamp(MIN_FOV, MAX_FOV),
+            Projection::Orthographic => {
+                self.ortho_height = (self.ortho_height * factor).clamp(MIN_ORTHO_HEIGHT, MAX_ORTHO_HEIGHT)
+            }
+            Projection::Equirectangular => {}
+        }
+    }
+
+    // Alternar perspectiva y ortográfica; la equirrectangular sólo se usa al exportar panoramas
+    pub fn toggle_projection(&mut self) {
+        self.projection = match self.projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic | Projection::Equirectangular => Projection::Perspective,
+        };
+    }
+
+    // Vista isométrica clásica (45° de giro, 35.26° de elevación) hacia el centro actual
+    pub fn isometric(&mut self) {
+        let distance = (self.eye - self.center).magnitude().max(ISOMETRIC_MIN_DISTANCE);
+        self.eye = self.center + Vec3::new(1.0, 1.0, 1.0).normalize() * distance;
+        self.projection = Projection::Orthographic;
+        self.ortho_height = ISOMETRIC_HEIGHT;
+    }
+
+    pub fn toggle_depth_of_field(&mut self) {
+        self.aperture = if self.aperture > 0.0 { 0.0 } else { DEFAULT_APERTURE };
+    }
+
+    pub fn toggle_motion_blur(&mut self) {
+        self.shutter = if self.shutter > 0.0 { 0.0 } else { DEFAULT_SHUTTER };
+    }
+
+    // Acercar (factor < 1) o alejar el plano enfocado; desactiva el autoenfoque
+    pub fn refocus(&mut self, factor: f32) {
+        self.autofocus = false;
+        self.focus_distance = (self.focus_distance * factor).max(MIN_FOCUS_DISTANCE);
+    }
+
+    // Girar la mirada desde el ojo (yaw a la derecha, pitch hacia arriba), manteniendo la distancia al centro
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let view = self.center - self.eye;
+        let distance = view.magnitude();
+
+        let current_yaw = view.z.atan2(view.x);
+        let current_pitch = (view.y / distance).clamp(-1.0, 1.0).asin();
+
+        let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
+        let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+
+        self.center = self.eye + Vec3::new(
+            distance * new_yaw.cos() * new_pitch.cos(),
+            distance * new_pitch.sin(),
+            distance * new_yaw.sin() * new_pitch.cos()
+        );
+    }
+
+    // Desplazar ojo y centro juntos: adelante y a los lados sobre el plano horizontal, arriba sobre `up`
+    pub fn fly(&mut self, forward: f32, right: f32, up: f32) {
+        let view = self.center - self.eye;
+        let horizontal = Vec3::new(view.x, 0.0, view.z);
+        if horizontal.magnitude() < 1e-4 {
+            return;
+        }
+        let horizontal = horizontal.normalize();
+        let side = horizontal.cross(&self.up).normalize();
+
+        let delta = horizontal * forward + side * right + self.up * up;
+        self.eye += delta;
+        self.center += delta;
+    }
+
+}
+
+// Mapeo concéntrico de Shirley del cuadrado unitario al disco unitario
+fn concentric_disk(sample: (f32, f32)) -> (f32, f32) {
+    let a = 2.0 * sample.0 - 1.0;
+    let b = 2.0 * sample.1 - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (radius, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (radius * theta.cos(), radius * theta.sin())
+}
@@ -0,0 +1,139 @@
+use nalgebra_glm::Vec3;
+use std::fs;
+
+use crate::camera::Camera;
+use crate::time_of_day::TimeOfDay;
+
+// Punto de control del recorrido: a los `time` segundos la cámara está en `eye` mirando a `target`
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32,
+    pub eye: Vec3,
+    pub target: Vec3,
+    // Campo de visión vertical en radianes
+    pub fov: f32,
+    // Hora del reloj; puede pasar de 24 para recorridos que cruzan la medianoche
+    pub hour: f32,
+}
+
+impl Keyframe {
+    // Colocar la cámara y el reloj en este punto del recorrido. Las horas cuentan desde el día
+    // `base_day`, fijado al empezar el recorrido, para que el contador de días no se desplace
+    pub fn apply(&self, camera: &mut Camera, time_of_day: &mut TimeOfDay, base_day: f32) {
+        camera.eye = self.eye;
+        camera.center = self.target;
+        camera.fov = self.fov;
+        time_of_day.set_elapsed(base_day + (self.hour - 6.0) / 24.0);
+    }
+}
+
+// Recorrido de cámara interpolado con splines de Catmull-Rom
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        CameraPath { keyframes }
+    }
+
+    pub fn load(file_path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(file_path).map_err(|error| format!("{}: {}", file_path, error))?;
+        Self::parse(&text).map_err(|error| format!("{}: {}", file_path, error))
+    }
+
+    // Una línea por keyframe con nueve números separados por espacios:
+    //   tiempo  ojo_x ojo_y ojo_z  objetivo_x objetivo_y objetivo_z  fov_en_grados  hora
+    // Las líneas vacías y las que empiezan con # se ignoran.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut keyframes = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values = line
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|error| format!("línea {}: {}", number + 1, error))?;
+            if values.len() != 9 {
+                return Err(format!("línea {}: se esperaban 9 valores y hay {}", number + 1, values.len()));
+            }
+
+            keyframes.push(Keyframe {
+                time: values[0],
+                eye: Vec3::new(values[1], values[2], values[3]),
+                target: Vec3::new(values[4], values[5], values[6]),
+                fov: values[7].to_radians(),
+                hour: values[8],
+            });
+        }
+
+        if keyframes.is_empty() {
+            return Err("el recorrido no tiene keyframes".to_string());
+        }
+        Ok(Self::new(keyframes))
+    }
+
+    pub fn duration(&self) -> f32 {
+        match (self.keyframes.first(), self.keyframes.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    // Estado interpolado a los `time` segundos desde el inicio del recorrido
+    pub fn sample(&self, time: f32) -> Keyframe {
+        let keyframes = &self.keyframes;
+        let time = keyframes[0].time + time.clamp(0.0, self.duration());
+
+        // Segmento [i, i + 1] que contiene el instante pedido
+        let last = keyframes.len() - 1;
+        let i = keyframes
+            .iter()
+            .rposition(|keyframe| keyframe.time <= time)
+            .unwrap_or(0)
+            .min(last.saturating_sub(1));
+        if last == 0 {
+            return keyframes[0];
+        }
+
+        // Los extremos se repiten para que la curva pase por el primer y el último keyframe
+        let k0 = &keyframes[i.saturating_sub(1)];
+        let k1 = &keyframes[i];
+        let k2 = &keyframes[i + 1];
+        let k3 = &keyframes[(i + 2).min(last)];
+
+        let span = k2.time - k1.time;
+        let t = if span > 0.0 { ((time - k1.time) / span).clamp(0.0, 1.0) } else { 0.0 };
+
+        Keyframe {
+            time,
+            eye: catmull_rom_vec3(&k0.eye, &k1.eye, &k2.eye, &k3.eye, t),
+            target: catmull_rom_vec3(&k0.target, &k1.target, &k2.target, &k3.target, t),
+            fov: catmull_rom(k0.fov, k1.fov, k2.fov, k3.fov, t),
+            hour: catmull_rom(k0.hour, k1.hour, k2.hour, k3.hour, t),
+        }
+    }
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+fn catmull_rom_vec3(p0: &Vec3, p1: &Vec3, p2: &Vec3, p3: &Vec3, t: f32) -> Vec3 {
+    Vec3::new(
+        catmull_rom(p0.x, p1.x, p2.x, p3.x, t),
+        catmull_rom(p0.y, p1.y, p2.y, p3.y, t),
+        catmull_rom(p0.z, p1.z, p2.z, p3.z, t),
+    )
+}
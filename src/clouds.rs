@@ -0,0 +1,192 @@
+use nalgebra_glm::Vec3;
+
+use crate::light::Light;
+use crate::media::henyey_greenstein;
+use crate::noise::{fbm, hash_vec3};
+use crate::scene::Scene;
+use crate::time_of_day::smoothstep;
+
+// Distancia horizontal desde el centro en la que las nubes se desvanecen hacia el horizonte
+const FADE_START: f32 = 60.0;
+const FADE_END: f32 = 140.0;
+const MAX_MARCH_DISTANCE: f32 = 150.0;
+const NOISE_OCTAVES: u32 = 3;
+// Muestras hacia la luz para el autosombreado y para las sombras sobre el terreno
+const LIGHT_SAMPLES: u32 = 3;
+const SHADOW_SAMPLES: u32 = 3;
+const ANISOTROPY: f32 = 0.3;
+const AMBIENT_STRENGTH: f32 = 0.6;
+// Los rayos dejan de avanzar cuando la nube ya tapa casi todo el fondo
+const MIN_TRANSMITTANCE: f32 = 0.03;
+// Cuánto cierran el cielo la lluvia y la nieve
+const WEATHER_COVERAGE: f32 = 0.7;
+
+// Capa de nubes volumétricas entre dos alturas, arrastrada por el viento
+#[derive(Debug, Clone, Copy)]
+pub struct CloudLayer {
+    pub enabled: bool,
+    pub bottom: f32,
+    pub top: f32,
+    // Fracción del cielo cubierta con el tiempo despejado, en [0, 1]
+    pub coverage: f32,
+    // Coeficiente de extinción en la parte más densa de las nubes
+    pub density: f32,
+    pub wind: Vec3,
+    // Frecuencia del ruido: valores menores dan nubes más grandes
+    pub scale: f32,
+    pub samples: u32,
+    offset: Vec3,
+}
+
+impl CloudLayer {
+    pub fn new(bottom: f32, top: f32, coverage: f32, density: f32, wind: Vec3, samples: u32) -> Self {
+        CloudLayer {
+            enabled: true,
+            bottom,
+            top,
+            coverage,
+            density,
+            wind,
+            scale: 0.08,
+            samples,
+            offset: Vec3::zeros(),
+        }
+    }
+
+    // Desplazar las nubes con el viento
+    pub fn update(&mut self, delta_seconds: f32) {
+        self.offset += self.wind * delta_seconds;
+    }
+
+    // La lluvia y la nieve cubren el cielo por encima de la cobertura base
+    fn effective_coverage(&self, scene: &Scene) -> f32 {
+        self.coverage + (1.0 - self.coverage) * WEATHER_COVERAGE * scene.weather.coverage
+    }
+
+    fn density_at(&self, point: &Vec3, coverage: f32) -> f32 {
+        let height = (point.y - self.bottom) / (self.top - self.bottom);
+        if !(0.0..=1.0).contains(&height) {
+            return 0.0;
+        }
+
+        // Base plana, cima redondeada y desvanecimiento a lo lejos
+        let profile = smoothstep(0.0, 0.15, height) * (1.0 - smoothstep(0.5, 1.0, height));
+        let horizontal = (point.x * point.x + point.z * point.z).sqrt();
+        let fade = 1.0 - smoothstep(FADE_START, FADE_END, horizontal);
+        if profile * fade <= 0.0 {
+            return 0.0;
+        }
+
+        let noise = fbm(&((point - self.offset) * self.scale), NOISE_OCTAVES);
+        let threshold = 1.0 - coverage;
+        let shape = ((noise - threshold) / (1.0 - threshold).max(0.05)).clamp(0.0, 1.0);
+        shape * profile * fade * self.density
+    }
+
+    // Tramo del rayo dentro de la capa, limitado a `max_distance`
+    fn slab_segment(&self, origin: &Vec3, direction: &Vec3, max_distance: f32) -> Option<(f32, f32)> {
+        let max_distance = max_distance.min(MAX_MARCH_DISTANCE);
+        let (near, far) = if direction.y.abs() < 1e-4 {
+            if origin.y < self.bottom || origin.y > self.top {
+                return None;
+            }
+            (0.0, max_distance)
+        } else {
+            let t0 = (self.bottom - origin.y) / direction.y;
+            let t1 = (self.top - origin.y) / direction.y;
+            (t0.min(t1).max(0.0), t0.max(t1).min(max_distance))
+        };
+
+        if near < far {
+            Some((near, far))
+        } else {
+            None
+        }
+    }
+
+    // Profundidad óptica de la nube entre dos distancias del rayo
+    fn optical_depth(&self, origin: &Vec3, direction: &Vec3, near: f32, far: f32, samples: u32, coverage: f32) -> f32 {
+        let step = (far - near) / samples as f32;
+        (0..samples)
+            .map(|i| {
+                let point = origin + direction * (near + (i as f32 + 0.5) * step);
+                self.density_at(&point, coverage) * step
+            })
+            .sum()
+    }
+
+    // Fracción de la luz que atraviesa las nubes entre un punto y una luz. Sólo el sol y la luna
+    // brillan desde fuera de la capa; como luces direccionales, el rayo cruza toda la capa aunque
+    // su posición en la órbita quede por debajo de las nubes
+    pub fn shadow(&self, point: &Vec3, light: &Light, scene: &Scene) -> f32 {
+        if !self.enabled || !light.is_celestial() {
+            return 1.0;
+        }
+        let coverage = self.effective_coverage(scene);
+        let (direction, distance) = light.direction_from(point);
+        match self.slab_segment(point, &direction, distance) {
+            Some((near, far)) => {
+                (-self.optical_depth(point, &direction, near, far, SHADOW_SAMPLES, coverage)).exp()
+            }
+            None => 1.0,
+        }
+    }
+
+    // Mezclar las nubes que cruza el rayo antes de `distance` sobre el color de fondo
+    pub fn apply(&self, color: [f32; 3], origin: &Vec3, direction: &Vec3, distance: f32, scene: &Scene) -> [f32; 3] {
+        if !self.enabled || self.samples == 0 {
+            return color;
+        }
+        let coverage = self.effective_coverage(scene);
+        if coverage <= 0.0 {
+            return color;
+        }
+        let Some((near, far)) = self.slab_segment(origin, direction, distance) else {
+            return color;
+        };
+
+        // La base de las nubes recibe la luz difusa del cielo
+        let sky = scene.weather.overcast(scene.time_of_day.sky.horizon_color(direction));
+        let ambient = sky.map(|channel| channel * AMBIENT_STRENGTH);
+
+        let step = (far - near) / self.samples as f32;
+        // Desplazamiento aleatorio por píxel para cambiar bandas por ruido
+        let jitter = hash_vec3(direction);
+        let mut transmittance = 1.0;
+        let mut scattered = [0.0; 3];
+
+        for i in 0..self.samples {
+            let point = origin + direction * (near + (i as f32 + jitter) * step);
+            let density = self.density_at(&point, coverage);
+            if density <= 0.0 {
+                continue;
+            }
+
+            // Luz del sol y la luna que llega a la muestra a través de la propia nube
+            let mut light = ambient;
+            for source in scene.lights.iter().filter(|source| source.is_celestial()) {
+                let (to_light, _) = source.direction_from(&point);
+                let Some((_, exit)) = self.slab_segment(&point, &to_light, f32::INFINITY) else {
+                    continue;
+                };
+                let depth = self.optical_depth(&point, &to_light, 0.0, exit, LIGHT_SAMPLES, coverage);
+                let phase = henyey_greenstein(direction.dot(&to_light), ANISOTROPY);
+                let amount = source.intensity * (-depth).exp() * phase;
+                for (out, channel) in light.iter_mut().zip(source.color.to_f32().iter()) {
+                    *out += channel * amount;
+                }
+            }
+
+            let extinction = (-density * step).exp();
+            for (out, channel) in scattered.iter_mut().zip(light.iter()) {
+                *out += transmittance * (1.0 - extinction) * channel;
+            }
+            transmittance *= extinction;
+            if transmittance < MIN_TRANSMITTANCE {
+                break;
+            }
+        }
+
+        [0, 1, 2].map(|channel| color[channel] * transmittance + scattered[channel])
+    }
+}
@@ -1,6 +1,10 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+// Gamma de la pantalla: los colores de 8 bits (texturas, materiales, framebuffer) están codificados
+// con ella y el trazador trabaja en luz lineal
+const GAMMA: f32 = 2.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     r: u8,
     g: u8,
@@ -9,10 +13,39 @@ pub struct Color {
 
 impl Color {
     // Constructor to initialize the color using r, g, b values
-    pub fn new(r: u8, g: u8, b: u8) -> Self {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
         Color { r, g, b }
     }
 
+    // Function to create a color from floating point components in the [0, 1] range
+    pub fn from_f32(r: f32, g: f32, b: f32) -> Self {
+        Color {
+            r: (r * 255.0).clamp(0.0, 255.0) as u8,
+            g: (g * 255.0).clamp(0.0, 255.0) as u8,
+            b: (b * 255.0).clamp(0.0, 255.0) as u8,
+        }
+    }
+
+    // Function to return the color as floating point components in the [0, 1] range
+    pub fn to_f32(self) -> [f32; 3] {
+        [
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+        ]
+    }
+
+    // Componentes en luz lineal, deshaciendo la corrección gamma del color de 8 bits
+    pub fn to_linear(self) -> [f32; 3] {
+        decode_gamma(self.to_f32())
+    }
+
+    // Color de 8 bits a partir de luz lineal: se limita a [0, 1] y se aplica la corrección gamma
+    pub fn from_linear(rgb: [f32; 3]) -> Self {
+        let [r, g, b] = rgb.map(|channel| channel.clamp(0.0, 1.0).powf(1.0 / GAMMA));
+        Color::from_f32(r, g, b)
+    }
+
     // Function to create a color from a hex value
     pub fn from_hex(hex: u32) -> Self {
         let r = ((hex >> 16) & 0xFF) as u8;
@@ -22,7 +55,7 @@ impl Color {
     }
 
     // Function to return the color as a hex value
-    pub fn to_hex(&self) -> u32 {
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 
@@ -35,6 +68,7 @@ impl Color {
         }
     }
     // New function to clamp the color values to ensure they are between 0 and 255
+    #[allow(clippy::unnecessary_min_or_max)]
     pub fn clamp(&self) -> Self {
         Color {
             r: self.r.min(255),
@@ -90,3 +124,26 @@ impl Color {
         Color { r, g, b }
     }
 }
+
+// Pasar a luz lineal un color en punto flotante pensado para verse tal cual en la pantalla
+pub fn decode_gamma(rgb: [f32; 3]) -> [f32; 3] {
+    rgb.map(|channel| channel.max(0.0).powf(GAMMA))
+}
+
+// Operaciones de los colores en punto flotante que usa el trazador. No se limitan a [0, 1] para
+// conservar la radiancia por encima del blanco hasta que el color se escribe en el framebuffer.
+pub fn add_rgb(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|channel| a[channel] + b[channel])
+}
+
+pub fn mul_rgb(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|channel| a[channel] * b[channel])
+}
+
+pub fn scale_rgb(rgb: [f32; 3], factor: f32) -> [f32; 3] {
+    rgb.map(|channel| channel * factor)
+}
+
+pub fn lerp_rgb(a: [f32; 3], b: [f32; 3], factor: f32) -> [f32; 3] {
+    [0, 1, 2].map(|channel| a[channel] + (b[channel] - a[channel]) * factor)
+}
@@ -1,5 +1,5 @@
 use nalgebra_glm::Vec3;
-use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::ray_intersect::{count_intersection_test, RayIntersect, Intersect};
 use crate::texture::Texture;
 use std::rc::Rc;
 use crate::material::Material;
@@ -12,10 +12,15 @@ pub struct Cube {
     pub top_texture: Rc<Texture>,     
     pub side_texture: Rc<Texture>,    
     pub bottom_texture: Rc<Texture>,  
+    // Identificadores de los pases auxiliares; empiezan en 1 y se asignan al armar la escena
+    pub block_id: u32,
+    pub material_id: u32,
 }
 
 impl RayIntersect for Cube {
     fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        count_intersection_test();
+
         // Cálculo de la intersección del rayo con el cubo
         let mut t_min = (self.min.x - ray_origin.x) / ray_direction.x;
         let mut t_max = (self.max.x - ray_origin.x) / ray_direction.x;
@@ -67,27 +72,28 @@ impl RayIntersect for Cube {
         let point_on_surface = ray_origin + ray_direction * t_min;
 
          // Calcular la textura adecuada según la cara del cubo
-        let color = if (point_on_surface.y - self.max.y).abs() < 1e-4 {
+        let (texture, u, v) = if (point_on_surface.y - self.max.y).abs() < 1e-4 {
             // Cara superior
             let u = (point_on_surface.x - self.min.x) / (self.max.x - self.min.x);
             let v = (point_on_surface.z - self.min.z) / (self.max.z - self.min.z);
-            self.top_texture.get_color(u, v)
+            (&self.top_texture, u, v)
         } else if (point_on_surface.y - self.min.y).abs() < 1e-4 {
             // Cara inferior
             let u = (point_on_surface.x - self.min.x) / (self.max.x - self.min.x);
             let v = (point_on_surface.z - self.min.z) / (self.max.z - self.min.z);
-            self.bottom_texture.get_color(u, v)
+            (&self.bottom_texture, u, v)
         } else if (point_on_surface.x - self.min.x).abs() < 1e-4 || (point_on_surface.x - self.max.x).abs() < 1e-4 {
             // Caras laterales izquierda y derecha
             let u = (point_on_surface.z - self.min.z) / (self.max.z - self.min.z);
             let v = (point_on_surface.y - self.min.y) / (self.max.y - self.min.y);
-            self.side_texture.get_color(u, v)
+            (&self.side_texture, u, v)
         } else {
             // Caras frontal y trasera
             let u = (point_on_surface.x - self.min.x) / (self.max.x - self.min.x);
             let v = (point_on_surface.y - self.min.y) / (self.max.y - self.min.y);
-            self.side_texture.get_color(u, v) // Usamos la textura lateral aquí
+            (&self.side_texture, u, v) // Usamos la textura lateral aquí
         };
+        let color = texture.get_color(u, v);
 
         // Crear un nuevo material usando el color calculado
         let material = Material {
@@ -99,11 +105,37 @@ impl RayIntersect for Cube {
         let normal = self.calculate_normal(point_on_surface);
 
         // Retornar la intersección con la textura aplicada
-        Intersect::new(point_on_surface, normal, t_min, material)
+        let mut intersect = Intersect::new(point_on_surface, normal, t_min, material);
+        intersect.uv = [u, v];
+        intersect
     }
 }
 
 impl Cube {
+    // Verificar si un punto está estrictamente dentro del cubo
+    pub fn contains(&self, point: &Vec3) -> bool {
+        point.x > self.min.x
+            && point.x < self.max.x
+            && point.y > self.min.y
+            && point.y < self.max.y
+            && point.z > self.min.z
+            && point.z < self.max.z
+    }
+
+    // Distancia a la que un rayo que parte desde dentro del cubo sale por una de sus caras
+    pub fn exit_distance(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> f32 {
+        let mut t_exit = f32::INFINITY;
+        for axis in 0..3 {
+            let t1 = (self.min[axis] - ray_origin[axis]) / ray_direction[axis];
+            let t2 = (self.max[axis] - ray_origin[axis]) / ray_direction[axis];
+            let t_far = t1.max(t2);
+            if t_far.is_finite() {
+                t_exit = t_exit.min(t_far);
+            }
+        }
+        t_exit.max(0.0)
+    }
+
     // Calcular la normal según la cara del cubo en la que se encuentra el punto de intersección
     fn calculate_normal(&self, point: Vec3) -> Vec3 {
         let epsilon = 1e-4;
@@ -0,0 +1,91 @@
+use minifb::Key;
+
+use crate::color::Color;
+
+// Distancia a la que la vista de distancia se oscurece a un tercio
+const DISTANCE_SCALE: f32 = 40.0;
+// Pruebas de intersección por objeto de la escena que corresponden al rojo del mapa de calor. Con
+// el rayo primario, las sombras y los rayos de luz de la niebla, un píxel hace unas 14 a 31
+const HEATMAP_RAYS: f32 = 32.0;
+// Rayos secundarios trazados para el píxel que corresponden al rojo de la vista de profundidad
+const REFLECTION_DEPTH_RAYS: f32 = 2.0;
+
+// Vistas de depuración que reemplazan el sombreado normal en `cast_ray`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugView {
+    Off,
+    // Normal de la superficie como color
+    Normals,
+    // Coordenadas de textura: u en rojo y v en verde
+    Uv,
+    // Distancia del rayo hasta la superficie, blanco cerca y negro lejos
+    Distance,
+    // Pruebas de intersección hechas para el píxel, de azul (pocas) a rojo (muchas)
+    Heatmap,
+    // Blanco donde llega la luz directa y negro en la sombra
+    ShadowOnly,
+    // Iluminación sin texturas, agua, niebla ni nubes
+    LightingOnly,
+    // Rayos secundarios que trazó el píxel, con la rampa del mapa de calor. El reflejo de Fresnel no
+    // traza rayos (mezcla el color hacia `fresnel_color`), así que hoy sólo cuenta la refracción del agua
+    ReflectionDepth,
+}
+
+impl DebugView {
+    // Teclas del 1 al 7 para cada vista y 0 para volver al sombreado normal
+    pub fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::Key0 => Some(DebugView::Off),
+            Key::Key1 => Some(DebugView::Normals),
+            Key::Key2 => Some(DebugView::Uv),
+            Key::Key3 => Some(DebugView::Distance),
+            Key::Key4 => Some(DebugView::Heatmap),
+            Key::Key5 => Some(DebugView::ShadowOnly),
+            Key::Key6 => Some(DebugView::LightingOnly),
+            Key::Key7 => Some(DebugView::ReflectionDepth),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugView::Off => "off",
+            DebugView::Normals => "normals",
+            DebugView::Uv => "uv",
+            DebugView::Distance => "distance",
+            DebugView::Heatmap => "heatmap",
+            DebugView::ShadowOnly => "shadow_only",
+            DebugView::LightingOnly => "lighting_only",
+            DebugView::ReflectionDepth => "reflection_depth",
+        }
+    }
+}
+
+pub fn distance_color(distance: f32) -> Color {
+    let value = (-distance / DISTANCE_SCALE).exp();
+    Color::from_f32(value, value, value)
+}
+
+// Pruebas hechas por objeto de la escena
+pub fn heatmap_color(tests: usize, object_count: usize) -> Color {
+    heat_ramp(tests as f32 / (object_count.max(1) as f32 * HEATMAP_RAYS))
+}
+
+pub fn reflection_depth_color(secondary_rays: u32) -> Color {
+    heat_ramp(secondary_rays as f32 / REFLECTION_DEPTH_RAYS)
+}
+
+// Rampa azul -> cian -> verde -> amarillo -> rojo para `t` en [0, 1]
+fn heat_ramp(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let ramp = [
+        Color::new(0, 0, 255),
+        Color::new(0, 255, 255),
+        Color::new(0, 255, 0),
+        Color::new(255, 255, 0),
+        Color::new(255, 0, 0),
+    ];
+    let position = t * (ramp.len() - 1) as f32;
+    let index = (position.floor() as usize).min(ramp.len() - 2);
+    ramp[index].lerp(ramp[index + 1], position - index as f32)
+}
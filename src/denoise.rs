@@ -0,0 +1,142 @@
+use crate::aov::AovBuffers;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Pesos del spline B3 que usa el filtro à-trous en cada eje
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+// Albedo mínimo con el que se separa la iluminación de la textura
+const MIN_ALBEDO: f32 = 0.01;
+
+// Filtro à-trous que respeta los bordes, guiado por las normales, la profundidad y el albedo
+#[derive(Debug, Clone, Copy)]
+pub struct Denoiser {
+    pub enabled: bool,
+    // Cada iteración duplica la separación entre las muestras del filtro
+    pub iterations: usize,
+    // Diferencia de luminancia tolerada entre vecinos; se reduce a la mitad en cada iteración
+    pub sigma_color: f32,
+    // Exponente del peso de las normales: cuanto mayor, más se respetan las aristas de los bloques
+    pub sigma_normal: f32,
+    // Diferencia de profundidad tolerada, relativa a la profundidad del píxel y a la separación
+    pub sigma_depth: f32,
+}
+
+impl Denoiser {
+    pub fn new(iterations: usize, sigma_color: f32, sigma_normal: f32, sigma_depth: f32) -> Self {
+        Denoiser {
+            enabled: false,
+            iterations,
+            sigma_color,
+            sigma_normal,
+            sigma_depth,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    // Filtrar la imagen en punto flotante de `aovs` y escribir el resultado en el framebuffer.
+    // Se filtra la iluminación (color dividido por el albedo) para no borrar las texturas.
+    pub fn apply(&self, aovs: &AovBuffers, framebuffer: &mut Framebuffer) {
+        let width = aovs.width;
+        let height = aovs.height;
+
+        let mut lighting: Vec<[f32; 3]> = aovs
+            .radiance
+            .iter()
+            .zip(aovs.albedo.iter())
+            .map(|(radiance, albedo)| demodulate(radiance, albedo))
+            .collect();
+        let mut filtered = lighting.clone();
+
+        for iteration in 0..self.iterations {
+            let step = 1 << iteration;
+            let sigma_color = self.sigma_color / (1 << iteration) as f32;
+
+            for y in 0..height {
+                for x in 0..width {
+                    let index = y * width + x;
+                    // El cielo no tiene superficie que guíe el filtro
+                    if !aovs.depth[index].is_finite() {
+                        filtered[index] = lighting[index];
+                        continue;
+                    }
+
+                    let center = lighting[index];
+                    let mut total = [0.0; 3];
+                    let mut total_weight = 0.0;
+                    for (j, ky) in KERNEL.iter().enumerate() {
+                        let sy = y as isize + (j as isize - 2) * step;
+                        if sy < 0 || sy >= height as isize {
+                            continue;
+                        }
+                        for (i, kx) in KERNEL.iter().enumerate() {
+                            let sx = x as isize + (i as isize - 2) * step;
+                            if sx < 0 || sx >= width as isize {
+                                continue;
+                            }
+
+                            let neighbor = sy as usize * width + sx as usize;
+                            let weight = kx * ky * self.edge_weight(aovs, index, neighbor, &center, &lighting[neighbor], step, sigma_color);
+                            for (sum, value) in total.iter_mut().zip(lighting[neighbor].iter()) {
+                                *sum += value * weight;
+                            }
+                            total_weight += weight;
+                        }
+                    }
+
+                    // El píxel central siempre tiene peso, así que `total_weight` no es cero
+                    filtered[index] = total.map(|sum| sum / total_weight);
+                }
+            }
+            std::mem::swap(&mut lighting, &mut filtered);
+        }
+
+        for y in 0..height.min(framebuffer.height) {
+            for x in 0..width.min(framebuffer.width) {
+                let index = y * width + x;
+                let color = remodulate(&lighting[index], &aovs.albedo[index]);
+                framebuffer.draw_pixel(x, y, Color::from_linear(color).to_hex());
+            }
+        }
+    }
+
+    // Peso de un vecino según lo parecidas que son su normal, su profundidad y su iluminación
+    #[allow(clippy::too_many_arguments)]
+    fn edge_weight(
+        &self,
+        aovs: &AovBuffers,
+        index: usize,
+        neighbor: usize,
+        center: &[f32; 3],
+        sample: &[f32; 3],
+        step: isize,
+        sigma_color: f32,
+    ) -> f32 {
+        let depth = aovs.depth[index];
+        let neighbor_depth = aovs.depth[neighbor];
+        if !neighbor_depth.is_finite() {
+            return 0.0;
+        }
+
+        let normal_weight = aovs.normal[index].dot(&aovs.normal[neighbor]).max(0.0).powf(self.sigma_normal);
+        let depth_weight = (-(depth - neighbor_depth).abs() / (self.sigma_depth * depth * step as f32 + 1e-4)).exp();
+        let color_weight = (-(luminance(center) - luminance(sample)).abs() / (sigma_color + 1e-4)).exp();
+        normal_weight * depth_weight * color_weight
+    }
+}
+
+fn luminance(color: &[f32; 3]) -> f32 {
+    0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+}
+
+fn demodulate(radiance: &[f32; 3], albedo: &Color) -> [f32; 3] {
+    let albedo = albedo.to_linear();
+    [0, 1, 2].map(|channel| radiance[channel] / albedo[channel].max(MIN_ALBEDO))
+}
+
+fn remodulate(lighting: &[f32; 3], albedo: &Color) -> [f32; 3] {
+    let albedo = albedo.to_linear();
+    [0, 1, 2].map(|channel| lighting[channel] * albedo[channel].max(MIN_ALBEDO))
+}
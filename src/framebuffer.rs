@@ -1,3 +1,7 @@
+use crate::color::Color;
+use crate::font::{glyph, ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::texture::Texture;
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
@@ -38,6 +42,121 @@ impl Framebuffer {
         }
     }
 
+    // Limpiar el framebuffer con el color de fondo
+    pub fn clear_background(&mut self) {
+        self.clear(self.background_color);
+    }
+
+    // Las figuras usan el color actual y coordenadas con signo, para que se puedan dibujar
+    // parcialmente fuera de la pantalla
+    fn plot(&mut self, x: isize, y: isize) {
+        if x >= 0 && y >= 0 {
+            self.point(x as usize, y as usize);
+        }
+    }
+
+    // Línea de Bresenham entre dos puntos, ambos incluidos
+    pub fn line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+
+        loop {
+            self.plot(x, y);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    // Contorno de un rectángulo con la esquina superior izquierda en (x, y)
+    pub fn rect(&mut self, x: isize, y: isize, width: isize, height: isize) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.line(x, y, right, y);
+        self.line(x, bottom, right, bottom);
+        self.line(x, y, x, bottom);
+        self.line(right, y, right, bottom);
+    }
+
+    pub fn fill_rect(&mut self, x: isize, y: isize, width: isize, height: isize) {
+        for row in y.max(0)..(y + height).min(self.height as isize) {
+            for column in x.max(0)..(x + width).min(self.width as isize) {
+                self.point(column as usize, row as usize);
+            }
+        }
+    }
+
+    // Contorno de un círculo con el algoritmo del punto medio
+    pub fn circle(&mut self, center_x: isize, center_y: isize, radius: isize) {
+        let (mut x, mut y) = (radius, 0);
+        let mut error = 1 - radius;
+        while x >= y {
+            for (dx, dy) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+                self.plot(center_x + dx, center_y + dy);
+            }
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    pub fn fill_circle(&mut self, center_x: isize, center_y: isize, radius: isize) {
+        for dy in -radius..=radius {
+            let half_width = ((radius * radius - dy * dy) as f32).sqrt() as isize;
+            self.fill_rect(center_x - half_width, center_y + dy, 2 * half_width + 1, 1);
+        }
+    }
+
+    // Mezclar un color con el píxel existente; `alpha` es la opacidad del color nuevo
+    pub fn blend_pixel(&mut self, x: usize, y: usize, color: u32, alpha: f32) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+            let blended = Color::from_hex(self.buffer[index]).lerp(Color::from_hex(color), alpha.clamp(0.0, 1.0));
+            self.buffer[index] = blended.to_hex();
+        }
+    }
+
+    pub fn blend_rect(&mut self, x: isize, y: isize, width: isize, height: isize, color: u32, alpha: f32) {
+        for row in y.max(0)..(y + height).min(self.height as isize) {
+            for column in x.max(0)..(x + width).min(self.width as isize) {
+                self.blend_pixel(column as usize, row as usize, color, alpha);
+            }
+        }
+    }
+
+    // Copiar una textura escalada al rectángulo dado, respetando su transparencia
+    pub fn blit(&mut self, texture: &Texture, x: isize, y: isize, width: isize, height: isize) {
+        for row in y.max(0)..(y + height).min(self.height as isize) {
+            let v = ((row - y) as f32 + 0.5) / height as f32;
+            for column in x.max(0)..(x + width).min(self.width as isize) {
+                let u = ((column - x) as f32 + 0.5) / width as f32;
+                let alpha = texture.get_alpha(u, v);
+                if alpha > 0.0 {
+                    self.blend_pixel(column as usize, row as usize, texture.get_color(u, v).to_hex(), alpha);
+                }
+            }
+        }
+    }
+
     pub fn set_background_color(&mut self, color: u32) {
         self.background_color = color;
     }
@@ -45,4 +164,28 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
+
+    // Escribir texto con la fuente de mapa de bits; (x, y) es la esquina superior izquierda
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, color: u32) {
+        for (index, character) in text.chars().enumerate() {
+            let rows = glyph(character);
+            let left = x + index * ADVANCE;
+            for (row, bits) in rows.iter().enumerate().take(GLYPH_HEIGHT) {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (0x10 >> column) != 0 {
+                        self.draw_pixel(left + column, y + row, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // Guardar el contenido como imagen; el formato sale de la extensión del archivo
+    pub fn save(&self, file_path: &str) -> image::ImageResult<()> {
+        let mut image = image::RgbImage::new(self.width as u32, self.height as u32);
+        for (pixel, color) in image.pixels_mut().zip(self.buffer.iter()) {
+            *pixel = image::Rgb([(color >> 16) as u8, (color >> 8) as u8, *color as u8]);
+        }
+        image.save(file_path)
+    }
 }
@@ -0,0 +1,170 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::cube::Cube;
+use crate::font::{ADVANCE, LINE_HEIGHT};
+use crate::framebuffer::Framebuffer;
+
+const MARGIN: usize = 4;
+const TEXT_COLOR: u32 = 0xFFFFFF;
+// Fondo semitransparente detrás del texto y del minimapa para que se lean sobre el cielo claro
+const PANEL_COLOR: u32 = 0x000000;
+const PANEL_OPACITY: f32 = 0.45;
+const MINIMAP_SIZE: isize = 96;
+const CAMERA_MARKER_COLOR: u32 = 0xFF4040;
+// Peso del último cuadro en los promedios, para que los números no salten en cada cuadro
+const SMOOTHING: f32 = 0.1;
+
+// Texto superpuesto con el rendimiento y el estado del render
+pub struct Hud {
+    pub visible: bool,
+    // Promedios suavizados, en segundos, del tiempo real entre cuadros (del que salen los FPS) y
+    // del tiempo de trabajo de cada cuadro sin la espera, y de los rayos por segundo de trabajo
+    pub frame_interval: f32,
+    pub work_time: f32,
+    pub rays_per_second: f32,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Hud {
+            visible: true,
+            frame_interval: 0.0,
+            work_time: 0.0,
+            rays_per_second: 0.0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Registrar un cuadro que llegó `frame_interval` segundos después del anterior, cuyo trabajo
+    // (sin la espera del presupuesto) tardó `work_time` segundos y trazó `rays` rayos primarios
+    pub fn update(&mut self, frame_interval: f32, work_time: f32, rays: usize) {
+        if frame_interval > 0.0 {
+            smooth(&mut self.frame_interval, frame_interval);
+        }
+        if work_time > 0.0 {
+            smooth(&mut self.work_time, work_time);
+            smooth(&mut self.rays_per_second, rays as f32 / work_time);
+        }
+    }
+
+    // Dibujar las estadísticas y después `lines` en la esquina superior izquierda
+    pub fn draw(&self, framebuffer: &mut Framebuffer, lines: &[String]) {
+        if !self.visible {
+            return;
+        }
+
+        let fps = if self.frame_interval > 0.0 { 1.0 / self.frame_interval } else { 0.0 };
+        let stats = [
+            format!("FPS {:.1}  {:.1} ms", fps, self.work_time * 1000.0),
+            format!("Rayos/s {}", abbreviate(self.rays_per_second)),
+        ];
+
+        let longest = stats.iter().chain(lines.iter()).map(|line| line.chars().count()).max().unwrap_or(0);
+        let line_count = stats.len() + lines.len();
+        framebuffer.blend_rect(
+            0,
+            0,
+            (2 * MARGIN + longest * ADVANCE) as isize,
+            (2 * MARGIN + line_count * LINE_HEIGHT) as isize,
+            PANEL_COLOR,
+            PANEL_OPACITY,
+        );
+
+        for (index, line) in stats.iter().chain(lines.iter()).enumerate() {
+            framebuffer.draw_text(MARGIN, MARGIN + index * LINE_HEIGHT, line, TEXT_COLOR);
+        }
+    }
+
+    // Mira en el centro de la pantalla, que es el punto que usa el autoenfoque
+    pub fn draw_crosshair(&self, framebuffer: &mut Framebuffer) {
+        if !self.visible {
+            return;
+        }
+
+        let center_x = framebuffer.width as isize / 2;
+        let center_y = framebuffer.height as isize / 2;
+        framebuffer.set_current_color(TEXT_COLOR);
+        framebuffer.line(center_x - 7, center_y, center_x - 3, center_y);
+        framebuffer.line(center_x + 3, center_y, center_x + 7, center_y);
+        framebuffer.line(center_x, center_y - 7, center_x, center_y - 3);
+        framebuffer.line(center_x, center_y + 3, center_x, center_y + 7);
+        framebuffer.circle(center_x, center_y, 10);
+    }
+
+    // Vista del diorama desde arriba (X hacia la derecha, Z hacia abajo) en la esquina superior
+    // derecha, con cada bloque dibujado con su textura superior y la cámara con su dirección
+    pub fn draw_minimap(&self, framebuffer: &mut Framebuffer, objects: &[Cube], camera: &Camera) {
+        if !self.visible || objects.is_empty() {
+            return;
+        }
+
+        // Límites en XZ de los bloques y de la cámara, para que la cámara siempre aparezca
+        let (mut min_x, mut max_x) = (camera.eye.x, camera.eye.x);
+        let (mut min_z, mut max_z) = (camera.eye.z, camera.eye.z);
+        for object in objects {
+            min_x = min_x.min(object.min.x);
+            max_x = max_x.max(object.max.x);
+            min_z = min_z.min(object.min.z);
+            max_z = max_z.max(object.max.z);
+        }
+
+        let left = framebuffer.width as isize - MARGIN as isize - MINIMAP_SIZE;
+        let top = MARGIN as isize;
+        let padding = 6;
+        let scale = (MINIMAP_SIZE - 2 * padding) as f32 / (max_x - min_x).max(max_z - min_z).max(1e-3);
+        let to_map = |point: &Vec3| {
+            (
+                left + padding + ((point.x - min_x) * scale).round() as isize,
+                top + padding + ((point.z - min_z) * scale).round() as isize,
+            )
+        };
+
+        framebuffer.blend_rect(left, top, MINIMAP_SIZE, MINIMAP_SIZE, PANEL_COLOR, PANEL_OPACITY);
+
+        // Los bloques más altos se dibujan al final para que tapen a los de abajo
+        let mut blocks: Vec<&Cube> = objects.iter().collect();
+        blocks.sort_by(|a, b| a.max.y.total_cmp(&b.max.y));
+        for block in blocks {
+            let (x0, z0) = to_map(&block.min);
+            let (x1, z1) = to_map(&block.max);
+            framebuffer.blit(&block.top_texture, x0, z0, (x1 - x0).max(1), (z1 - z0).max(1));
+        }
+
+        framebuffer.set_current_color(TEXT_COLOR);
+        framebuffer.rect(left, top, MINIMAP_SIZE, MINIMAP_SIZE);
+
+        let forward = camera.center - camera.eye;
+        let heading = Vec3::new(forward.x, 0.0, forward.z);
+        let (eye_x, eye_z) = to_map(&camera.eye);
+        framebuffer.set_current_color(CAMERA_MARKER_COLOR);
+        if heading.magnitude() > 1e-4 {
+            let tip = heading.normalize() * 10.0;
+            framebuffer.line(eye_x, eye_z, eye_x + tip.x.round() as isize, eye_z + tip.z.round() as isize);
+        }
+        framebuffer.fill_circle(eye_x, eye_z, 3);
+    }
+}
+
+// 1234567 -> "1.23M"
+fn abbreviate(value: f32) -> String {
+    if value >= 1e6 {
+        format!("{:.2}M", value / 1e6)
+    } else if value >= 1e3 {
+        format!("{:.1}K", value / 1e3)
+    } else {
+        format!("{:.0}", value)
+    }
+}
+
+// Promedio móvil exponencial que empieza en el primer valor
+fn smooth(average: &mut f32, value: f32) {
+    if *average == 0.0 {
+        *average = value;
+    } else {
+        *average += (value - *average) * SMOOTHING;
+    }
+}
@@ -1,29 +1,82 @@
 
-use crate::color::Color;
+use crate::color::{add_rgb, mul_rgb, scale_rgb, Color};
 use nalgebra_glm::Vec3;
 use crate::cube::Cube;
 use crate::ray_intersect::{Intersect, RayIntersect};
 use crate::material::Material;
+
+// Fuente de cada luz de la escena
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Sun,
+    Moon,
+    Glowstone,
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct Light {
     pub position: Vec3,
     pub color: Color,
     pub intensity: f32,
+    pub kind: LightKind,
 }
 
 impl Light {
-    pub fn new(position: Vec3, color: Color, intensity: f32) -> Self {
+    pub fn new(position: Vec3, color: Color, intensity: f32, kind: LightKind) -> Self {
         Light {
             position,
             color,
             intensity,
+            kind,
+        }
+    }
+
+    // El sol y la luna están en el cielo, lejos de la escena; las demás luces están dentro de ella
+    pub fn is_celestial(&self) -> bool {
+        matches!(self.kind, LightKind::Sun | LightKind::Moon)
+    }
+
+    // Dirección y distancia hacia la luz desde un punto. El sol y la luna se tratan como luces
+    // direccionales, igual que en el cielo: su posición sólo da la dirección desde el centro
+    pub fn direction_from(&self, point: &Vec3) -> (Vec3, f32) {
+        if self.is_celestial() {
+            (self.position.normalize(), f32::INFINITY)
+        } else {
+            let to_light = self.position - point;
+            (to_light.normalize(), to_light.magnitude())
         }
     }
 }
 
+// Llenar `lights` con las luces interpoladas entre dos cuadros. Cada luz se empareja con la de la
+// misma fuente en el cuadro anterior; las que acaban de aparecer se usan tal como están ahora
+pub fn interpolate_lights(previous: &[Light], current: &[Light], time: f32, lights: &mut Vec<Light>) {
+    lights.clear();
+    lights.extend(current.iter().map(|to| match previous.iter().find(|from| from.kind == to.kind) {
+        Some(from) => Light {
+            position: from.position + (to.position - from.position) * time,
+            color: from.color.lerp(to.color, time),
+            intensity: from.intensity + (to.intensity - from.intensity) * time,
+            kind: to.kind,
+        },
+        None => *to,
+    }));
+}
+
 pub fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
     incident - 2.0 * incident.dot(normal) * normal
 }
 
+// Dirección refractada según la ley de Snell (`eta` = n1 / n2), o None si hay reflexión total
+pub fn refract(incident: &Vec3, normal: &Vec3, eta: f32) -> Option<Vec3> {
+    let cos_i = -incident.dot(normal).clamp(-1.0, 1.0);
+    let k = 1.0 - eta * eta * (1.0 - cos_i * cos_i);
+    if k < 0.0 {
+        return None;
+    }
+    Some(incident * eta + normal * (eta * cos_i - k.sqrt()))
+}
+
 const SHADOW_BIAS: f32 = 1e-4;
 
 pub fn cast_shadow(
@@ -41,20 +94,30 @@ pub fn cast_shadow(
         intersect.point + offset_normal
     };
 
-    let mut shadow_intensity = 0.0;
-
-    // Revisar si algún objeto está bloqueando la luz
-    for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance  {
-            let distance_ratio = shadow_intersect.distance / light_distance;
-            shadow_intensity = 1.0 - distance_ratio.powf(2.0).min(1.0);
-            break;
+    match blocker_distance(&shadow_ray_origin, &light_dir, light_distance, objects) {
+        Some(distance) => {
+            let distance_ratio = distance / light_distance;
+            1.0 - distance_ratio.powf(2.0).min(1.0)
         }
+        None => 0.0,
     }
-    shadow_intensity
 }
 
+// Distancia al primer bloque que tapa la luz a lo largo del rayo, antes de `max_distance`
+pub fn blocker_distance(origin: &Vec3, direction: &Vec3, max_distance: f32, objects: &[Cube]) -> Option<f32> {
+    // Revisar si algún objeto está bloqueando la luz (el agua deja pasar la luz)
+    objects
+        .iter()
+        .filter(|object| object.material.casts_shadow())
+        .map(|object| object.ray_intersect(origin, direction))
+        .find(|shadow_intersect| shadow_intersect.is_intersecting && shadow_intersect.distance < max_distance)
+        .map(|shadow_intersect| shadow_intersect.distance)
+}
+
+// Luz directa de todas las luces con sus sombras, en punto flotante y sin límite superior. Devuelve
+// también la fracción de la luz bloqueada
+// (0 con el punto totalmente iluminado, 1 totalmente a la sombra), ponderada por su intensidad
+#[allow(clippy::too_many_arguments)]
 pub fn calculate_lighting(
     point: &Vec3,
     normal: &Vec3,
@@ -64,24 +127,33 @@ pub fn calculate_lighting(
     material_albedo: [f32; 2],
     lights: &[Light],
     objects: &[Cube], 
-) -> Color {
-    let mut final_color = Color::new(0, 0, 0);
+    transmittance: impl Fn(&Light) -> f32,
+) -> ([f32; 3], f32) {
+    let mut final_color = [0.0; 3];
+    // Intensidad de la luz directa bloqueada por bloques o nubes, para la máscara de sombras
+    let mut blocked = 0.0;
+    let mut total = 0.0;
 
     for light in lights {
-        let intersect = Intersect::new(*point, *normal, 0.0, Material::new([1.0, 0.0], 0.5, 0.0, 0.0, Color::new(255, 255, 255), Color::new(255, 255, 255)));
+        let intersect = Intersect::new(*point, *normal, 0.0, Material::new([1.0, 0.0], 0.5, 0.0, 0.0, Color::new(255, 255, 255), Color::new(255, 255, 255), 0.0));
         let shadow_intensity = cast_shadow(&intersect, light, objects);
-        let light_intensity = light.intensity * (1.0 - shadow_intensity);
+        let light_intensity = light.intensity * (1.0 - shadow_intensity) * transmittance(light);
+        blocked += light.intensity - light_intensity;
+        total += light.intensity;
         let light_dir = (light.position - *point).normalize();
         let reflect_dir = reflect(&-light_dir, normal);
 
+        // El color de la luz tiñe tanto el difuso como el especular
+        let light_color = light.color.to_f32();
         let diffuse_intensity: f32 = normal.dot(&light_dir).max(0.0);
-        let diffuse: Color = material_diffuse.scale(diffuse_intensity * material_albedo[0]) * light_intensity;
+        let diffuse = scale_rgb(mul_rgb(material_diffuse.to_linear(), light_color), diffuse_intensity * material_albedo[0] * light_intensity);
 
-        let specular_intensity = reflect_dir.dot(&view_dir).max(0.0).powf(material_specular);
-        let specular: Color = Color::new(255, 255, 255).scale(specular_intensity * material_albedo[1]) * light_intensity;
+        let specular_intensity = reflect_dir.dot(view_dir).max(0.0).powf(material_specular);
+        let specular = scale_rgb(light_color, specular_intensity * material_albedo[1] * light_intensity);
 
-        final_color = final_color + diffuse + specular;
+        final_color = add_rgb(add_rgb(final_color, diffuse), specular);
     }
 
-    final_color
-}
\ No newline at end of file
+    let shadow = if total > 0.0 { blocked / total } else { 0.0 };
+    (final_color, shadow)
+}
@@ -1,131 +1,457 @@
+mod ambient;
+mod aov;
 mod camera;
+mod camera_path;
+mod clouds;
 mod color;
 mod cube;
+mod debug_view;
+mod denoise;
+mod font;
 mod framebuffer;
+mod hud;
 mod light;
 mod material;
+mod media;
+mod night_sky;
+mod noise;
 mod ray_intersect;
+mod resolution;
+mod scene;
+mod settings;
+mod sky;
+mod stereo;
+mod temporal;
 mod texture;
+mod time_of_day;
+mod weather;
+mod wireframe;
 
-use minifb::{Key, Window, WindowOptions};
-use nalgebra_glm::{normalize, Vec3};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Scale, ScaleMode, Window, WindowOptions};
+use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 use std::rc::Rc;
-use std::time::Duration;
-
-use crate::camera::Camera;
-use crate::color::Color;
+use std::time::{Duration, Instant};
+
+use crate::ambient::{AmbientOcclusion, AoMode};
+use crate::aov::{AovAccumulator, AovBuffers, AovKind, AovSample};
+use crate::camera::{Camera, CameraMode, Projection, CUBE_FACES};
+use crate::camera_path::CameraPath;
+use crate::clouds::CloudLayer;
+use crate::color::{add_rgb, decode_gamma, lerp_rgb, scale_rgb, Color};
 use crate::cube::Cube;
+use crate::debug_view::{distance_color, heatmap_color, reflection_depth_color, DebugView};
+use crate::denoise::Denoiser;
 use crate::framebuffer::Framebuffer;
-use crate::light::{calculate_lighting, Light};
+use crate::hud::Hud;
+use crate::light::{calculate_lighting, interpolate_lights, refract, Light, LightKind};
 use crate::material::Material;
-use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::media::{HeightFog, WaterMedium, FOG_SKY_DISTANCE};
+use crate::noise::hash3;
+use crate::ray_intersect::{intersection_tests, Intersect, RayIntersect};
+use crate::resolution::{upscale_bilinear, ResolutionScaler};
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::stereo::{StereoMode, StereoRig};
+use crate::temporal::TemporalCache;
 use crate::texture::Texture;
+use crate::time_of_day::TimeOfDay;
+use crate::weather::{Weather, WeatherKind};
+use crate::wireframe::WireframeOverlay;
 
+// Índice de refracción del agua
+const WATER_IOR: f32 = 1.33;
+// Color neutro con el que la vista de sólo iluminación reemplaza las texturas
+const LIGHTING_ONLY_ALBEDO: Color = Color::new(200, 200, 200);
 
 fn fresnel_effect(normal: Vec3, view_dir: Vec3, f0: f32) -> f32 {
     let cos_theta = normal.dot(&view_dir).max(0.0);
     f0 + (1.0 - f0) * (1.0 - cos_theta).powi(5)
 }
 
-pub fn cast_ray(
+// Intersección más cercana a lo largo del rayo; `skip_transparent` ignora los volúmenes de agua
+fn closest_hit<'a>(
     ray_origin: &Vec3,
     ray_direction: &Vec3,
-    objects: &[Cube],
-    skybox: &[Cube],
-    lights: &[Light],
-    camera: &Camera,
-    is_night: bool,
-) -> Color {
-    let mut closest_intersect = Intersect::empty();
+    objects: &'a [Cube],
+    skip_transparent: bool,
+) -> Option<(Intersect, &'a Cube)> {
+    let mut closest: Option<(Intersect, &Cube)> = None;
     let mut zbuffer = f32::INFINITY;
 
-    // Verificar intersección con los objetos de la escena
     for object in objects {
+        if skip_transparent && object.material.transparency > 0.0 {
+            continue;
+        }
         let intersect = object.ray_intersect(ray_origin, ray_direction);
         if intersect.is_intersecting && intersect.distance < zbuffer {
             zbuffer = intersect.distance;
-            closest_intersect = intersect;
+            closest = Some((intersect, object));
         }
     }
 
-    // Si no hay intersección con ningún objeto de la escena
-    if !closest_intersect.is_intersecting {
-        // Renderizar el Skybox en lugar de un color sólido
-        for skybox_face in skybox {
-            let intersect = skybox_face.ray_intersect(ray_origin, ray_direction);
-            if intersect.is_intersecting {
-                return intersect.material.diffuse; 
-            }
+    closest
+}
+
+// Índice del cubo que se ve bajo el cursor en una imagen de `width` x `height`
+fn object_under_cursor(window: &Window, camera: &Camera, objects: &[Cube], width: usize, height: usize) -> Option<usize> {
+    // `get_mouse_pos` divide entre la escala de la ventana, que con Scale::FitScreen no es la del
+    // framebuffer; se parte de los píxeles de la ventana y se pasan a los del framebuffer
+    let (window_x, window_y) = window.get_unscaled_mouse_pos(MouseMode::Discard)?;
+    let (window_width, window_height) = window.get_size();
+    if window_width == 0 || window_height == 0 {
+        return None;
+    }
+    let mouse_x = window_x * width as f32 / window_width as f32;
+    let mouse_y = window_y * height as f32 / window_height as f32;
+    let screen_x = (2.0 * mouse_x) / width as f32 - 1.0;
+    let screen_y = -(2.0 * mouse_y) / height as f32 + 1.0;
+    let (origin, direction) = camera.ray(screen_x, screen_y, width as f32 / height as f32, (0.5, 0.5));
+    let (_, object) = closest_hit(&origin, &direction, objects, false)?;
+    objects.iter().position(|candidate| std::ptr::eq(candidate, object))
+}
+
+// Iluminación directa, ambiental y emisiva de un punto de la superficie en punto flotante, junto
+// con la fracción de la luz directa que queda a la sombra
+fn shade(
+    intersect: &Intersect,
+    object: &Cube,
+    view_dir: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+) -> ([f32; 3], f32) {
+    let (direct_color, shadow) = calculate_lighting(
+        &intersect.point,
+        &intersect.normal,
+        view_dir,
+        intersect.material.diffuse,
+        intersect.material.specular,
+        [intersect.material.albedo[0], intersect.material.albedo[1]],
+        scene.lights,
+        scene.objects,
+        // Las nubes proyectan sombras en movimiento sobre el terreno
+        |light| scene.clouds.shadow(&intersect.point, light, scene),
+    );
+
+    // Luz ambiental atenuada por la oclusión de los bloques cercanos
+    let ambient = &settings.ambient;
+    let occlusion = ambient.occlusion(intersect, object, scene.objects);
+    let ambient_level = ambient.level * scene.time_of_day.ambient_factor() * occlusion;
+    let albedo = intersect.material.diffuse.to_linear();
+    let ambient_color = scale_rgb(albedo, ambient_level);
+
+    // Los materiales emisivos se encienden al caer la noche
+    let emission = intersect.material.emission * scene.time_of_day.emission();
+    let emissive_color = scale_rgb(albedo, emission);
+
+    (add_rgb(add_rgb(direct_color, ambient_color), emissive_color), shadow)
+}
+
+// Color del cielo, oscurecido cuando el clima lo cubre
+fn sky_color(ray_direction: &Vec3, scene: &Scene) -> [f32; 3] {
+    scene.weather.overcast(scene.time_of_day.sky.color(ray_direction))
+}
+
+// Color de lo que se ve a través del agua, atenuado por la distancia recorrida en ella
+fn trace_through_water(
+    intersect: &Intersect,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+) -> [f32; 3] {
+    let refracted = refract(ray_direction, &intersect.normal, 1.0 / WATER_IOR).unwrap_or(*ray_direction);
+    let origin = intersect.point + refracted * 1e-3;
+
+    let (below_color, distance) = match closest_hit(&origin, &refracted, scene.objects, true) {
+        Some((below, object)) => (
+            shade(&below, object, &-refracted, scene, settings).0,
+            below.distance,
+        ),
+        None => (sky_color(&refracted, scene), f32::INFINITY),
+    };
+
+    let path = settings.water.path_length(&origin, &refracted, distance, scene.objects);
+    settings.water.absorb(below_color, path)
+}
+
+// Mezclar sobre el color la partícula de clima más cercana, si el rayo cruza alguna
+fn composite_particles(
+    color: [f32; 3],
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    distance: f32,
+    scene: &Scene,
+) -> [f32; 3] {
+    match scene.weather.intersect(ray_origin, ray_direction, distance) {
+        Some(particle) => {
+            let brightness = scene.time_of_day.ambient_factor() * scene.weather.light_factor();
+            lerp_rgb(color, scale_rgb(particle.color.to_linear(), brightness), particle.opacity)
         }
+        None => color,
+    }
+}
 
-        return if is_night {
-            Color::new(10, 10, 30) 
-        } else {
-            Color::new(63, 96, 188) 
-        };
+// Llenar los pases auxiliares con la superficie que ve el rayo y la sombra que calculó `shade`
+fn fill_aov(aov: &mut AovSample, intersect: &Intersect, object: &Cube, shadow: f32) {
+    aov.depth = intersect.distance;
+    aov.normal = intersect.normal;
+    aov.albedo = intersect.material.diffuse;
+    aov.material_id = object.material_id;
+    aov.block_id = object.block_id;
+    aov.shadow = shadow;
+}
+
+// Sombreado de las vistas de depuración; None con el sombreado normal, el mapa de calor y la
+// profundidad de reflexión, que se calculan a partir del sombreado normal
+fn debug_shade(
+    view: DebugView,
+    intersect: &Intersect,
+    object: &Cube,
+    shadow: f32,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+) -> Option<[f32; 3]> {
+    let color = match view {
+        DebugView::Off | DebugView::Heatmap | DebugView::ReflectionDepth => return None,
+        // La iluminación ya sale en luz lineal
+        DebugView::LightingOnly => {
+            let mut untextured = *intersect;
+            untextured.material.diffuse = LIGHTING_ONLY_ALBEDO;
+            return Some(shade(&untextured, object, &-ray_direction, scene, settings).0);
+        }
+        DebugView::Normals => {
+            let normal = intersect.normal * 0.5 + Vec3::new(0.5, 0.5, 0.5);
+            [normal.x, normal.y, normal.z]
+        }
+        DebugView::Uv => [intersect.uv[0], intersect.uv[1], 0.0],
+        DebugView::Distance => distance_color(intersect.distance).to_f32(),
+        DebugView::ShadowOnly => [1.0 - shadow; 3],
+    };
+    // Los demás colores se eligen para verse tal cual en la pantalla
+    Some(decode_gamma(color))
+}
+
+// Radiancia que llega por el rayo, en punto flotante y sin limitar a [0, 1]. `aov` recibe, si se
+// pide, los datos de la primera superficie para los pases auxiliares
+pub fn cast_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    settings: &RenderSettings,
+    aov: Option<&mut AovSample>,
+) -> [f32; 3] {
+    // Mapa de calor: se sombrea normalmente y se cuentan las pruebas de intersección que hizo el rayo
+    if settings.debug == DebugView::Heatmap {
+        let tests_before = intersection_tests();
+        let shaded = RenderSettings { debug: DebugView::Off, ..*settings };
+        cast_ray(ray_origin, ray_direction, scene, &shaded, aov);
+        return heatmap_color(intersection_tests() - tests_before, scene.objects.len()).to_linear();
     }
 
+    // Profundidad de reflexión: se sombrea normalmente y se cuentan los rayos secundarios trazados
+    if settings.debug == DebugView::ReflectionDepth {
+        let mut depth_sample = AovSample::empty();
+        let sample = aov.unwrap_or(&mut depth_sample);
+        let shaded = RenderSettings { debug: DebugView::Off, ..*settings };
+        cast_ray(ray_origin, ray_direction, scene, &shaded, Some(&mut *sample));
+        return reflection_depth_color(sample.secondary_rays).to_linear();
+    }
+
+    // Con la cámara bajo el agua el rayo atraviesa primero el medio
+    let underwater = settings.water.contains(ray_origin, scene.objects);
+
+    // Si no hay intersección con ningún objeto de la escena
+    let Some((closest_intersect, closest_object)) =
+        closest_hit(ray_origin, ray_direction, scene.objects, underwater)
+    else {
+        // Las vistas de depuración dejan el cielo en negro
+        if settings.debug != DebugView::Off {
+            return [0.0; 3];
+        }
+
+        // Renderizar el cielo analítico y las nubes en la dirección del rayo
+        let color = sky_color(ray_direction, scene);
+        let color = scene.clouds.apply(color, ray_origin, ray_direction, FOG_SKY_DISTANCE, scene);
+        let color = composite_particles(color, ray_origin, ray_direction, FOG_SKY_DISTANCE, scene);
+        return settings.fog.apply(color, ray_origin, ray_direction, FOG_SKY_DISTANCE, scene);
+    };
+
     // Si hay intersección, calcular la iluminación y el fresnel
-    let view_dir = (camera.eye - closest_intersect.point).normalize();
-    let final_color: Color = calculate_lighting(
-        &closest_intersect.point,
-        &closest_intersect.normal,
-        &view_dir,
-        closest_intersect.material.diffuse,
-        closest_intersect.material.specular,
-        [
-            closest_intersect.material.albedo[0],
-            closest_intersect.material.albedo[1],
-        ],
-        lights,
-        objects,
-    );
+    let view_dir = -ray_direction;
+    let (mut final_color, shadow) = shade(&closest_intersect, closest_object, &view_dir, scene, settings);
+
+    let mut aov = aov;
+    if let Some(aov) = aov.as_deref_mut() {
+        fill_aov(aov, &closest_intersect, closest_object, shadow);
+    }
+
+    if let Some(color) = debug_shade(
+        settings.debug,
+        &closest_intersect,
+        closest_object,
+        shadow,
+        ray_direction,
+        scene,
+        settings,
+    ) {
+        return color;
+    }
+
+    // El agua deja ver, teñido y atenuado, lo que hay debajo
+    let transparency = closest_intersect.material.transparency;
+    if transparency > 0.0 {
+        let transmitted = trace_through_water(&closest_intersect, ray_direction, scene, settings);
+        if let Some(aov) = aov {
+            aov.secondary_rays += 1;
+        }
+        final_color = lerp_rgb(final_color, transmitted, transparency);
+    }
 
     let f0 = closest_intersect.material.reflectivity;
     let fresnel = fresnel_effect(closest_intersect.normal, view_dir, f0);
     let fresnel_intensity = closest_intersect.material.reflectivity;
-    let reflected_color = closest_intersect.material.fresnel_color;
-    let final_color_with_fresnel = final_color.lerp(reflected_color, fresnel * fresnel_intensity);
+    let reflected_color = closest_intersect.material.fresnel_color.to_linear();
+    let mut final_color = lerp_rgb(final_color, reflected_color, fresnel * fresnel_intensity);
+
+    if underwater {
+        let path = settings.water.path_length(ray_origin, ray_direction, closest_intersect.distance, scene.objects);
+        final_color = settings.water.absorb(final_color, path);
+    }
+
+    let final_color = scene.clouds.apply(
+        final_color,
+        ray_origin,
+        ray_direction,
+        closest_intersect.distance,
+        scene,
+    );
+
+    let final_color = composite_particles(
+        final_color,
+        ray_origin,
+        ray_direction,
+        closest_intersect.distance,
+        scene,
+    );
+    settings.fog.apply(final_color, ray_origin, ray_direction, closest_intersect.distance, scene)
+}
 
-    final_color_with_fresnel
+// Tamaños de las imágenes panorámicas exportadas
+const PANORAMA_WIDTH: usize = 2048;
+const CUBE_FACE_SIZE: usize = 512;
+
+// Renderizar un panorama equirectangular 2:1 desde el ojo de la cámara y guardarlo en un archivo
+fn save_panorama(file_path: &str, scene: &Scene, camera: &Camera, settings: &RenderSettings) -> image::ImageResult<()> {
+    let mut framebuffer = Framebuffer::new(PANORAMA_WIDTH, PANORAMA_WIDTH / 2);
+    render(&mut framebuffer, scene, &camera.panorama(), settings, None);
+    framebuffer.save(file_path)
 }
 
+// Renderizar las seis caras de un cube map desde el ojo de la cámara como px.png, nx.png, ...
+fn save_cube_map(output_dir: &str, scene: &Scene, camera: &Camera, settings: &RenderSettings) -> image::ImageResult<()> {
+    std::fs::create_dir_all(output_dir)?;
+    let mut framebuffer = Framebuffer::new(CUBE_FACE_SIZE, CUBE_FACE_SIZE);
+    for (name, forward, up) in CUBE_FACES {
+        render(&mut framebuffer, scene, &camera.cube_face(forward, up), settings, None);
+        framebuffer.save(&format!("{}/{}.png", output_dir, name))?;
+    }
+    Ok(())
+}
+
+// Distancia al objeto bajo el centro de la pantalla, usada por el autoenfoque
+fn center_focus_distance(camera: &Camera, objects: &[Cube]) -> Option<f32> {
+    let (ray_origin, ray_direction) = camera.ray(0.0, 0.0, 1.0, (0.5, 0.5));
+    closest_hit(&ray_origin, &ray_direction, objects, false).map(|(intersect, _)| intersect.distance)
+}
+
+// Color promediado de un píxel; `aov` recibe los pases auxiliares promediados entre sus muestras
+fn render_pixel(
+    x: usize,
+    y: usize,
+    (width, height): (usize, usize),
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+    aov: Option<&mut AovSample>,
+) -> [f32; 3] {
+    let aspect_ratio = width as f32 / height as f32;
+    let screen_x = (2.0 * x as f32) / width as f32 - 1.0;
+    let screen_y = -(2.0 * y as f32) / height as f32 + 1.0;
+
+    // Con profundidad de campo o desenfoque de movimiento se promedian varias muestras por píxel
+    let samples = camera.samples_per_pixel();
+
+    let mut accumulated = [0.0; 3];
+    // Los pases auxiliares se promedian entre las muestras igual que la radiancia
+    let mut accumulated_aov = AovAccumulator::new();
+    // Luces del instante de cada muestra, reutilizadas entre muestras
+    let mut lights = Vec::new();
+    for sample in 0..samples {
+        let seed = 3 * sample as i32;
+        let lens_sample = (hash3(x as i32, y as i32, seed), hash3(x as i32, y as i32, seed + 1));
+
+        // Cámara y luces en un instante aleatorio dentro del intervalo del obturador
+        let posed_camera;
+        let (posed, moment) = if camera.shutter > 0.0 {
+            let time = camera.shutter_time(hash3(x as i32, y as i32, seed + 2));
+            posed_camera = camera.at_time(time);
+            interpolate_lights(scene.previous_lights, scene.lights, time, &mut lights);
+            (&posed_camera, Scene { lights: &lights, ..*scene })
+        } else {
+            (camera, *scene)
+        };
+        let (ray_origin, ray_direction) = posed.ray(screen_x, screen_y, aspect_ratio, lens_sample);
+
+        let mut sample_aov = AovSample::empty();
+        let color = cast_ray(
+            &ray_origin,
+            &ray_direction,
+            &moment,
+            settings,
+            aov.is_some().then_some(&mut sample_aov),
+        );
+        for (total, channel) in accumulated.iter_mut().zip(color.iter()) {
+            *total += channel / samples as f32;
+        }
+
+        if aov.is_some() {
+            // Profundidad lineal: distancia proyectada sobre el eje de la cámara
+            if posed.projection != Projection::Equirectangular {
+                let forward = (posed.center - posed.eye).normalize();
+                sample_aov.depth *= ray_direction.dot(&forward);
+            }
+            accumulated_aov.add(&sample_aov);
+        }
+    }
+
+    if let Some(aov) = aov {
+        accumulated_aov.finish(aov);
+    }
+    accumulated
+}
+
+// Con `aovs` se llenan también los pases auxiliares (profundidad, normales, etc.)
 pub fn render(
     framebuffer: &mut Framebuffer,
-    skybox: &[Cube],
-    objects: &[Cube],
+    scene: &Scene,
     camera: &Camera,
-    lights: &[Light],
-    is_night: bool,
+    settings: &RenderSettings,
+    mut aovs: Option<&mut AovBuffers>,
 ) {
-    framebuffer.clear(0x000000);
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
+    framebuffer.clear_background();
+    let size = (framebuffer.width, framebuffer.height);
 
     for y in 0..framebuffer.height {
         for x in 0..framebuffer.width {
-            let screen_x = (2.0 * x as f32) / width - 1.0;
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
-
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
-
-            let ray_direction = normalize(&Vec3::new(screen_x, screen_y, -1.0));
-            let rotated_direction = camera.base_change(&ray_direction);
-
-        let pixel_color = cast_ray(
-            &camera.eye,
-            &rotated_direction,
-            objects,
-            skybox,
-            lights,
-            &camera,
-            is_night,
-        );
+            let mut pixel_aov = AovSample::empty();
+            let aov = if aovs.is_some() { Some(&mut pixel_aov) } else { None };
+            let accumulated = render_pixel(x, y, size, scene, camera, settings, aov);
+            if let Some(aovs) = aovs.as_deref_mut() {
+                pixel_aov.radiance = accumulated;
+                aovs.set(x, y, &pixel_aov);
+            }
+        let pixel_color = Color::from_linear(accumulated);
 
         framebuffer.set_current_color(pixel_color.to_hex());
         framebuffer.point(x, y);
@@ -133,6 +459,19 @@ pub fn render(
 }
 }
 
+// Numerar los bloques de la escena para los pases auxiliares. Un material se identifica por el
+// primer bloque que lo usa.
+fn assign_ids(objects: &mut [Cube]) {
+    for index in 0..objects.len() {
+        let first_with_material = objects
+            .iter()
+            .position(|other| other.material == objects[index].material)
+            .unwrap_or(index);
+        objects[index].block_id = index as u32 + 1;
+        objects[index].material_id = first_with_material as u32 + 1;
+    }
+}
+
 pub fn create_voxelized_cube(
     min: Vec3,
     max: Vec3,
@@ -169,7 +508,9 @@ pub fn create_voxelized_cube(
                     top_texture: Rc::clone(&top_texture),
                     side_texture: Rc::clone(&side_texture),
                     bottom_texture: Rc::clone(&bottom_texture),
-                    material: material.clone(),
+                    material,
+                    block_id: 0,
+                    material_id: 0,
                 };
 
                 cubes.push(cube);
@@ -180,100 +521,104 @@ pub fn create_voxelized_cube(
     cubes
 }
 
-pub fn create_skybox(
-    sky_front: Rc<Texture>,
-    sky_back: Rc<Texture>,
-    sky_left: Rc<Texture>,
-    sky_right: Rc<Texture>,
-    sky_top: Rc<Texture>,
-    sky_bottom: Rc<Texture>,
-    size: f32,
-) -> Vec<Cube> {
-    let half_size = size / 2.0;
-    let skybox_material = Material {
-        diffuse: Color::new(255, 255, 255),
-        albedo: [1.0, 0.0],
-        specular: 0.0,
-        transparency: 0.0,
-        reflectivity: 0.0,
-        fresnel_color: Color::new(255, 255, 255),
-    };
-
-    // Cubo del frente
-    let front = create_voxelized_cube(
-        Vec3::new(-half_size, -half_size, half_size),
-        Vec3::new(half_size, half_size, half_size + 0.01),
-        Rc::clone(&sky_front),
-        Rc::clone(&sky_front),
-        Rc::clone(&sky_front),
-        skybox_material.clone(),
-        size,
-    );
-
-    // Cubo de atrás
-    let back = create_voxelized_cube(
-        Vec3::new(-half_size, -half_size, -half_size),
-        Vec3::new(half_size, half_size, -half_size - 0.01),
-        Rc::clone(&sky_back),
-        Rc::clone(&sky_back),
-        Rc::clone(&sky_back),
-        skybox_material.clone(),
-        size,
-    );
+// Renderizar la vista de la cámara, o la de los dos ojos si el rig estéreo está activo
+fn render_view(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+    stereo: &StereoRig,
+    aovs: Option<&mut AovBuffers>,
+) {
+    // Los pases auxiliares sólo se llenan con una única vista
+    match stereo.mode {
+        StereoMode::Off => render(framebuffer, scene, camera, settings, aovs),
+        StereoMode::SideBySide => {
+            let (left, right) = stereo.eyes(camera);
+            let half_width = framebuffer.width / 2;
+            let mut eye_buffer = Framebuffer::new(half_width, framebuffer.height);
+            framebuffer.clear_background();
+            for (eye, offset) in [(left, 0), (right, half_width)] {
+                render(&mut eye_buffer, scene, &eye, settings, None);
+                for y in 0..framebuffer.height {
+                    for x in 0..half_width {
+                        framebuffer.draw_pixel(x + offset, y, eye_buffer.buffer[y * half_width + x]);
+                    }
+                }
+            }
+        }
+        StereoMode::Anaglyph => {
+            let (left, right) = stereo.eyes(camera);
+            let mut right_buffer = Framebuffer::new(framebuffer.width, framebuffer.height);
+            render(framebuffer, scene, &left, settings, None);
+            render(&mut right_buffer, scene, &right, settings, None);
+
+            // Anaglifo de medio color: el rojo es la luminancia del ojo izquierdo para evitar
+            // que los objetos rojos se vean sólo con un ojo
+            for (pixel, right_pixel) in framebuffer.buffer.iter_mut().zip(right_buffer.buffer.iter()) {
+                let left_rgb = Color::from_hex(*pixel).to_f32();
+                let luminance = 0.299 * left_rgb[0] + 0.587 * left_rgb[1] + 0.114 * left_rgb[2];
+                let red = (luminance * 255.0).round() as u32;
+                *pixel = (red << 16) | (right_pixel & 0x00FFFF);
+            }
+        }
+    }
+}
 
-    // Cubo de la izquierda
-    let left = create_voxelized_cube(
-        Vec3::new(-half_size - 0.01, -half_size, -half_size),
-        Vec3::new(-half_size, half_size, half_size),
-        Rc::clone(&sky_left),
-        Rc::clone(&sky_left),
-        Rc::clone(&sky_left),
-        skybox_material.clone(),
-        size,
-    );
+// Ventana redimensionable, o sin bordes y encima de las demás en pantalla completa. minifb no tiene
+// pantalla completa exclusiva: se usa la escala más grande de la ventana que cabe en la pantalla.
+fn open_window(width: usize, height: usize, fullscreen: bool) -> Window {
+    let options = if fullscreen {
+        WindowOptions {
+            borderless: true,
+            title: false,
+            topmost: true,
+            scale: Scale::FitScreen,
+            ..WindowOptions::default()
+        }
+    } else {
+        WindowOptions {
+            resize: true,
+            scale_mode: ScaleMode::UpperLeft,
+            ..WindowOptions::default()
+        }
+    };
 
-    // Cubo de la derecha
-    let right = create_voxelized_cube(
-        Vec3::new(half_size, -half_size, -half_size),
-        Vec3::new(half_size + 0.01, half_size, half_size),
-        Rc::clone(&sky_right),
-        Rc::clone(&sky_right),
-        Rc::clone(&sky_right),
-        skybox_material.clone(),
-        size,
-    );
+    let mut window = Window::new("Cherry Blossom Biome", width, height, options).unwrap();
+    if fullscreen {
+        window.set_position(0, 0);
+    }
+    window
+}
 
-    // Cubo de arriba
-    let top = create_voxelized_cube(
-        Vec3::new(-half_size, half_size, -half_size),
-        Vec3::new(half_size, half_size + 0.01, half_size),
-        Rc::clone(&sky_top),
-        Rc::clone(&sky_top),
-        Rc::clone(&sky_top),
-        skybox_material.clone(),
-        size,
-    );
+// Luces del cuadro: sol y luna atenuados por el clima, más la glowstone al oscurecer
+fn frame_lights(time_of_day: &TimeOfDay, weather: &Weather) -> Vec<Light> {
+    // El cielo cubierto atenúa la luz del sol y la luna
+    let mut lights = time_of_day.lights();
+    for light in lights.iter_mut() {
+        light.intensity *= weather.light_factor();
+    }
 
-    // Cubo de abajo
-    let bottom = create_voxelized_cube(
-        Vec3::new(-half_size, -half_size - 0.01, -half_size),
-        Vec3::new(half_size, -half_size, half_size),
-        Rc::clone(&sky_bottom),
-        Rc::clone(&sky_bottom),
-        Rc::clone(&sky_bottom),
-        skybox_material.clone(),
-        size,
-    );
+    let glowstone_intensity = 0.1 * time_of_day.emission();
+    if glowstone_intensity > 0.0 {
+        let glowstone_light = Light {
+            position: Vec3::new(7.0, 6.375, -7.125), 
+            color: Color::new(255, 223, 0),      
+            intensity: glowstone_intensity,
+            kind: LightKind::Glowstone,
+        };
+        lights.push(glowstone_light);
+    }
 
-    let mut skybox = Vec::new();
-    skybox.extend(front);
-    skybox.extend(back);
-    skybox.extend(left);
-    skybox.extend(right);
-    skybox.extend(top);
-    skybox.extend(bottom);
+    lights
+}
 
-    skybox
+// Valor que sigue a una opción de la línea de comandos, p. ej. `--day-length 600`
+fn argument_value(args: &[String], option: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == option)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
 }
 
 fn main() {
@@ -285,17 +630,6 @@ fn main() {
 
     let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
 
-    let mut window = Window::new(
-        "Cherry Blossom Biome",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
-
-    let sky_texture = Rc::new(Texture::new("src/textures/sky.jpg"));
-    let sky_texture2 = Rc::new(Texture::new("src/textures/sky2.png"));
-
     let grass_texture = Rc::new(Texture::new("src/textures/grass_top.png"));
     let grass_side_texture = Rc::new(Texture::new("src/textures/grass_side.png"));
     let dirt_texture = Rc::new(Texture::new("src/textures/dirt.png"));
@@ -313,6 +647,7 @@ fn main() {
         0.1,
         Color::new(34, 139, 34),
         Color::new(255, 255, 255),
+        0.0,
     );
     let wood_material = Material::new(
         [0.6, 0.2],
@@ -321,6 +656,7 @@ fn main() {
         0.2,
         Color::new(160, 82, 45),
         Color::new(200, 200, 200),
+        0.0,
     );
     let leaves_material = Material::new(
         [0.5, 0.1],
@@ -329,6 +665,7 @@ fn main() {
         0.1,
         Color::new(255, 182, 193),
         Color::new(255, 200, 220),
+        0.0,
     );
     let water_material = Material::new(
         [0.4, 0.3],
@@ -337,6 +674,7 @@ fn main() {
         0.5,
         Color::new(0, 0, 255),
         Color::new(63, 96, 188),
+        0.0,
     );
     let glowstone_material = Material::new(
         [1.0, 0.9],
@@ -345,18 +683,9 @@ fn main() {
         0.5,
         Color::new(255, 215, 0),
         Color::new(255, 255, 200),
+        1.0,
     );
 
-    let skybox = Rc::new(create_skybox(
-        Rc::clone(&sky_texture2),
-        Rc::clone(&sky_texture),
-        Rc::clone(&sky_texture),
-        Rc::clone(&sky_texture),
-        Rc::clone(&sky_texture2),
-        Rc::clone(&sky_texture),
-        100.0,
-    ));
-
     let base_blocks_left = create_voxelized_cube(
         Vec3::new(-10.0, -5.5, -10.0),
         Vec3::new(-2.0, 0.0, 10.0),
@@ -504,7 +833,6 @@ fn main() {
     );
 
     let mut objects = Vec::new();
-    objects.extend(skybox.iter().cloned());
     objects.extend(base_blocks_left);
     objects.extend(base_blocks_under);
     objects.extend(base_blocks_right);
@@ -519,6 +847,7 @@ fn main() {
     objects.extend(bridge_base);
     objects.extend(post_blocks);
     objects.extend(glowstone_blocks);
+    assign_ids(&mut objects);
     println!("Número total de objetos: {}", objects.len());
 
     let mut camera = Camera::new(
@@ -529,80 +858,641 @@ fn main() {
 
     let rotation_speed = PI / 10.0;
 
-    let mut time = 0.0;
+    // Vuelo libre (F cambia de controlador): WASD para moverse, espacio y Ctrl para subir y bajar,
+    // Shift para correr y arrastrar con el ratón (o las flechas) para mirar
+    let mut camera_mode = CameraMode::Orbit;
+    let fly_speed = 8.0;
+    let sprint_factor = 3.0;
+    let look_speed = 1.5;
+    let mouse_sensitivity = 0.005;
+    let mut last_mouse: Option<(f32, f32)> = None;
+
+    // Luz ambiental con oclusión: O cambia entre apagado, suavizado por vértice y trazado de rayos
+    let ambient = AmbientOcclusion::new(0.25, AoMode::SmoothLighting, 2.0, 8);
+    // Niebla de altura con rayos de luz (G la activa, [ y ] cambian la densidad) y agua que absorbe el rojo
+    let fog = HeightFog::new(0.004, 0.15, 0.0, 8, 0.6);
+    let water = WaterMedium::new([0.45, 0.12, 0.06]);
+    let mut settings = RenderSettings::new(ambient, fog, water);
+
+    // Clima (R cambia entre despejado, lluvia y nieve); los pétalos caen de las copas de los árboles
+    let petal_emitters = vec![
+        (Vec3::new(-9.5, 7.0, -9.5), Vec3::new(-3.5, 12.5, -3.5)),
+        (Vec3::new(4.5, 5.0, 4.5), Vec3::new(10.5, 10.5, 10.5)),
+    ];
+    let mut weather = Weather::new(WeatherKind::Clear, petal_emitters);
+
+    // Capa de nubes sobre el diorama, empujada por el viento (C la activa)
+    let mut clouds = CloudLayer::new(16.0, 22.0, 0.45, 0.6, Vec3::new(1.5, 0.0, 0.5), 16);
+
+    let args: Vec<String> = std::env::args().collect();
+
+    // El día empieza con el amanecer y dura --day-length segundos reales (120 por defecto)
+    let day_length: f32 = match argument_value(&args, "--day-length") {
+        None => 120.0,
+        Some(value) => match value.parse::<f32>() {
+            Ok(day_length) if day_length.is_finite() && day_length > 0.0 => day_length,
+            _ => {
+                println!("--day-length debe ser una duración en segundos mayor que cero: {}", value);
+                std::process::exit(1);
+            }
+        },
+    };
+    let mut time_of_day = TimeOfDay::new(0.0, day_length);
+
+    // Recorridos de cámara: --path elige el archivo (T lo reproduce en la ventana) y
+    // --render <carpeta> lo renderiza sin ventana como una secuencia de PNG a --fps cuadros por segundo
+    let path_file = argument_value(&args, "--path").unwrap_or_else(|| "paths/flythrough.txt".to_string());
+    let fps: f32 = match argument_value(&args, "--fps") {
+        None => 30.0,
+        Some(value) => match value.parse::<f32>() {
+            Ok(fps) if fps.is_finite() && fps > 0.0 => fps,
+            _ => {
+                println!("--fps debe ser un número de cuadros por segundo mayor que cero: {}", value);
+                std::process::exit(1);
+            }
+        },
+    };
+    // --shutter <fracción> activa el desenfoque de movimiento (B lo alterna en la ventana); la
+    // fracción del intervalo entre cuadros va de 0 a 1
+    if let Some(value) = argument_value(&args, "--shutter") {
+        camera.shutter = match value.parse::<f32>() {
+            Ok(shutter) if (0.0..=1.0).contains(&shutter) => shutter,
+            _ => {
+                println!("--shutter debe ser una fracción del intervalo entre cuadros entre 0 y 1: {}", value);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    // Rig estéreo (Y cambia el modo, Inicio/Fin la convergencia, Insert/Supr la separación de los ojos);
+    // --stereo sbs|anaglyph lo activa también en los recorridos renderizados sin ventana
+    let mut stereo = StereoRig::new(1.0, 35.0);
+    if let Some(value) = argument_value(&args, "--stereo") {
+        stereo.mode = match StereoRig::parse_mode(&value) {
+            Some(mode) => mode,
+            None => {
+                println!("--stereo debe ser off, sbs, side-by-side o anaglyph: {}", value);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    // Filtro de ruido para las imágenes con pocas muestras (U lo alterna en la ventana, --denoise en los recorridos)
+    let mut denoiser = Denoiser::new(5, 0.5, 64.0, 0.05);
+    denoiser.enabled = args.iter().any(|arg| arg == "--denoise");
+
+    if let Some(output_dir) = argument_value(&args, "--render") {
+        let path = CameraPath::load(&path_file).unwrap_or_else(|error| panic!("No se pudo cargar el recorrido {}", error));
+        std::fs::create_dir_all(&output_dir).expect("Failed to create the output directory");
+
+        let frame_count = (path.duration() * fps).floor() as usize + 1;
+        let base_day = time_of_day.day();
+        path.sample(0.0).apply(&mut camera, &mut time_of_day, base_day);
+        let mut previous_lights = frame_lights(&time_of_day, &weather);
+        // El filtro necesita los pases auxiliares, que sólo se llenan sin estéreo
+        let denoise = denoiser.enabled && stereo.mode == StereoMode::Off;
+        let mut aovs = AovBuffers::new(framebuffer_width, framebuffer_height);
+        for frame in 0..frame_count {
+            let frame_delta = if frame == 0 { 0.0 } else { 1.0 / fps };
+            camera.remember_pose();
+            path.sample(frame as f32 / fps).apply(&mut camera, &mut time_of_day, base_day);
+            weather.update(frame_delta);
+            clouds.update(frame_delta);
+
+            let lights = frame_lights(&time_of_day, &weather);
+            let scene = Scene {
+                objects: &objects,
+                lights: &lights,
+                previous_lights: &previous_lights,
+                time_of_day: &time_of_day,
+                weather: &weather,
+                clouds: &clouds,
+            };
+            render_view(&mut framebuffer, &scene, &camera, &settings, &stereo, denoise.then_some(&mut aovs));
+            if denoise {
+                denoiser.apply(&aovs, &mut framebuffer);
+            }
+
+            let file_name = format!("{}/frame_{:05}.png", output_dir, frame + 1);
+            framebuffer.save(&file_name).expect("Failed to save frame");
+            println!("Cuadro {}/{}: {}", frame + 1, frame_count, file_name);
+            previous_lights = lights;
+        }
+        return;
+    }
+
+    // Imágenes sin ventana: --panorama <archivo> y --cubemap <carpeta> (F5 y F6 en la ventana),
+    // --exr <archivo> y --hdr <archivo> con la vista de la cámara en punto flotante (F8 en la ventana)
+    let panorama_file = argument_value(&args, "--panorama");
+    let cube_map_dir = argument_value(&args, "--cubemap");
+    let exr_file = argument_value(&args, "--exr");
+    let hdr_file = argument_value(&args, "--hdr");
+    if panorama_file.is_some() || cube_map_dir.is_some() || exr_file.is_some() || hdr_file.is_some() {
+        weather.update(0.0);
+        let lights = frame_lights(&time_of_day, &weather);
+        let scene = Scene {
+            objects: &objects,
+            lights: &lights,
+            previous_lights: &lights,
+            time_of_day: &time_of_day,
+            weather: &weather,
+            clouds: &clouds,
+        };
+        if let Some(file_path) = panorama_file {
+            save_panorama(&file_path, &scene, &camera, &settings).expect("Failed to save panorama");
+            println!("Panorama guardado en {}", file_path);
+        }
+        if let Some(output_dir) = cube_map_dir {
+            save_cube_map(&output_dir, &scene, &camera, &settings).expect("Failed to save cube map");
+            println!("Cube map guardado en {}", output_dir);
+        }
+        if exr_file.is_some() || hdr_file.is_some() {
+            let mut aovs = AovBuffers::new(framebuffer_width, framebuffer_height);
+            render(&mut framebuffer, &scene, &camera, &settings, Some(&mut aovs));
+            if let Some(file_path) = exr_file {
+                aovs.save_exr(&file_path).expect("Failed to save EXR");
+                println!("Imagen EXR guardada en {}", file_path);
+            }
+            if let Some(file_path) = hdr_file {
+                aovs.save_hdr(&file_path).expect("Failed to save HDR");
+                println!("Imagen HDR guardada en {}", file_path);
+            }
+        }
+        return;
+    }
+
+    // Recorrido en reproducción, segundos transcurridos y día en el que empezó
+    let mut playback: Option<(CameraPath, f32, f32)> = None;
+
+    // Pases auxiliares: Tab cambia el pase que se ve, F7 los guarda todos en aovs/ y F8 guarda
+    // render.exr (con los pases como capas) y render.hdr
+    let mut aovs = AovBuffers::new(framebuffer_width, framebuffer_height);
+    let mut aov_view = AovKind::Beauty;
+
+    // Reproyección temporal (J): traza sólo parte de los píxeles y reutiliza el cuadro anterior
+    let mut temporal = TemporalCache::new(1);
+
+    // Resolución dinámica (H): se renderiza en `render_buffer` al tamaño que permite el presupuesto
+    // de `frame_delay` y se escala a la ventana
+    let mut scaler = ResolutionScaler::new(frame_delay.as_secs_f32(), 0.25);
+    let mut render_buffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+
+    // Estadísticas y estado del render sobre la imagen (F1 las muestra u oculta)
+    let mut hud = Hud::new();
+
+    // Aristas de las cajas de los cubos sobre la imagen (E), con el cubo bajo el cursor resaltado
+    let mut wireframe = WireframeOverlay::new();
+
+    // La ventana se puede redimensionar y F11 alterna la pantalla completa; el framebuffer y los
+    // buffers auxiliares se vuelven a crear con el tamaño de la ventana
+    let mut window = open_window(window_width, window_height, false);
+    let mut fullscreen = false;
+    let mut windowed_size = (window_width, window_height);
+
+    let mut last_frame = Instant::now();
+    let mut previous_lights = frame_lights(&time_of_day, &weather);
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
-        time += 0.1; 
+        let now = Instant::now();
+        let delta_seconds = (now - last_frame).as_secs_f32();
+        last_frame = now;
+        camera.remember_pose();
+
+        // Controles del reloj: P pausa, +/- velocidad, coma/punto retroceden o adelantan, N mediodía, M medianoche
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            time_of_day.toggle_pause();
+            println!("Reloj en pausa: {}", time_of_day.paused);
+        }
 
-        let sun_angle = time % (2.0 * PI); 
-        let sun_position = Vec3::new(
-            15.0 * sun_angle.cos(), 
-            25.0 * sun_angle.sin(), 
-            15.0,                  
-        );
+        if window.is_key_pressed(Key::Equal, KeyRepeat::No) {
+            time_of_day.speed_up();
+            println!("Velocidad del reloj: x{}", time_of_day.speed);
+        }
+
+        if window.is_key_pressed(Key::Minus, KeyRepeat::No) {
+            time_of_day.slow_down();
+            println!("Velocidad del reloj: x{}", time_of_day.speed);
+        }
+
+        if window.is_key_down(Key::Comma) {
+            time_of_day.scrub(delta_seconds, -1.0);
+        }
+
+        if window.is_key_down(Key::Period) {
+            time_of_day.scrub(delta_seconds, 1.0);
+        }
 
-        let moon_angle = (sun_angle + PI) % (2.0 * PI); 
-        let moon_position = Vec3::new(15.0 * moon_angle.cos(), 25.0 * moon_angle.sin(), 15.0);
+        if window.is_key_pressed(Key::N, KeyRepeat::No) {
+            time_of_day.jump_to_hour(12.0);
+        }
 
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            time_of_day.jump_to_hour(0.0);
+        }
+
+        time_of_day.update(delta_seconds);
+
+        // T reproduce o detiene el recorrido de cámara, que manda sobre la cámara y el reloj
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            playback = match playback {
+                Some(_) => None,
+                None => match CameraPath::load(&path_file) {
+                    Ok(path) => Some((path, 0.0, time_of_day.day())),
+                    Err(error) => {
+                        println!("No se pudo cargar el recorrido {}", error);
+                        None
+                    }
+                },
+            };
+            println!("Recorrido en reproducción: {}", playback.is_some());
+        }
+
+        if let Some((path, elapsed, base_day)) = playback.as_mut() {
+            *elapsed += delta_seconds;
+            path.sample(*elapsed).apply(&mut camera, &mut time_of_day, *base_day);
+            if *elapsed > path.duration() {
+                playback = None;
+                println!("Recorrido terminado");
+            }
+        }
 
-        let (light_position, light_color, light_intensity) = if sun_angle < PI {
-            (sun_position, Color::new(255, 255, 224), 1.0)
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            weather.next_kind();
+            println!("Clima: {:?}", weather.kind);
+        }
+
+        weather.update(delta_seconds);
+
+        if window.is_key_pressed(Key::C, KeyRepeat::No) {
+            clouds.enabled = !clouds.enabled;
+            println!("Nubes: {}", clouds.enabled);
+        }
+
+        clouds.update(delta_seconds);
+
+        let lights = frame_lights(&time_of_day, &weather);
+
+        if window.is_key_pressed(Key::F, KeyRepeat::No) {
+            camera_mode = camera_mode.toggle();
+            println!("Cámara: {:?}", camera_mode);
+        }
+
+        match camera_mode {
+            CameraMode::Orbit => {
+                if window.is_key_down(Key::Left) {
+                    camera.orbit(rotation_speed, 0.0);
+                }
+
+                if window.is_key_down(Key::Right) {
+                    camera.orbit(-rotation_speed, 0.0);
+                }
+
+                if window.is_key_down(Key::Up) {
+                    camera.orbit(0.0, -rotation_speed);
+                }
+
+                if window.is_key_down(Key::Down) {
+                    camera.orbit(0.0, rotation_speed);
+                }
+                if window.is_key_down(Key::X) {
+                    camera.zoom(1.0);
+                }
+
+                if window.is_key_down(Key::Z) {
+                    camera.zoom(-1.0);
+                }
+            }
+            CameraMode::FreeFly => {
+                let axis = |positive: Key, negative: Key| {
+                    window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+                };
+                let mut speed = fly_speed * delta_seconds;
+                if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) {
+                    speed *= sprint_factor;
+                }
+                camera.fly(
+                    axis(Key::W, Key::S) * speed,
+                    axis(Key::D, Key::A) * speed,
+                    axis(Key::Space, Key::LeftCtrl) * speed,
+                );
+
+                let turn = look_speed * delta_seconds;
+                camera.look(axis(Key::Right, Key::Left) * turn, axis(Key::Up, Key::Down) * turn);
+            }
+        }
+
+        // Mirar arrastrando con el botón izquierdo del ratón en vuelo libre
+        let mouse = window.get_mouse_pos(MouseMode::Pass);
+        if camera_mode == CameraMode::FreeFly && window.get_mouse_down(MouseButton::Left) {
+            if let (Some((x, y)), Some((last_x, last_y))) = (mouse, last_mouse) {
+                camera.look((x - last_x) * mouse_sensitivity, (last_y - y) * mouse_sensitivity);
+            }
+        }
+        last_mouse = mouse;
+
+        // Proyección: RePág/AvPág cambian el FOV, V alterna perspectiva y ortográfica, I vista isométrica
+        if window.is_key_down(Key::PageUp) {
+            camera.zoom_fov(0.97);
+        }
+
+        if window.is_key_down(Key::PageDown) {
+            camera.zoom_fov(1.03);
+        }
+
+        if window.is_key_pressed(Key::V, KeyRepeat::No) {
+            camera.toggle_projection();
+            println!("Proyección: {:?}", camera.projection);
+        }
+
+        if window.is_key_pressed(Key::I, KeyRepeat::No) {
+            camera.isometric();
+            println!("Vista isométrica");
+        }
+
+        // Profundidad de campo: L la activa, K alterna el autoenfoque, ; y ' acercan o alejan el foco
+        if window.is_key_pressed(Key::L, KeyRepeat::No) {
+            camera.toggle_depth_of_field();
+            println!("Apertura de la lente: {}", camera.aperture);
+        }
+
+        if window.is_key_pressed(Key::K, KeyRepeat::No) {
+            camera.autofocus = !camera.autofocus;
+            println!("Autoenfoque: {}", camera.autofocus);
+        }
+
+        if window.is_key_pressed(Key::Semicolon, KeyRepeat::Yes) {
+            camera.refocus(0.9);
+            println!("Distancia de enfoque: {:.2}", camera.focus_distance);
+        }
+
+        if window.is_key_pressed(Key::Apostrophe, KeyRepeat::Yes) {
+            camera.refocus(1.1);
+            println!("Distancia de enfoque: {:.2}", camera.focus_distance);
+        }
+
+        if camera.autofocus {
+            if let Some(distance) = center_focus_distance(&camera, &objects) {
+                camera.focus_distance = distance;
+            }
+        }
+
+        if window.is_key_pressed(Key::B, KeyRepeat::No) {
+            camera.toggle_motion_blur();
+            println!("Obturador: {}", camera.shutter);
+        }
+
+        if window.is_key_pressed(Key::H, KeyRepeat::No) {
+            scaler.toggle();
+            println!("Resolución dinámica: {}", scaler.enabled);
+        }
+
+        if window.is_key_pressed(Key::J, KeyRepeat::No) {
+            temporal.next_interval();
+            println!("Reproyección temporal: 1 de cada {} píxeles", temporal.interval);
+        }
+
+        // Vistas de depuración: 1-7 eligen una y 0 vuelve al sombreado normal
+        let debug_keys = [Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7];
+        for key in debug_keys {
+            if let (true, Some(view)) = (window.is_key_pressed(key, KeyRepeat::No), DebugView::from_key(key)) {
+                settings.debug = view;
+                temporal.reset();
+                println!("Vista de depuración: {}", view.name());
+            }
+        }
+
+        if window.is_key_pressed(Key::U, KeyRepeat::No) {
+            denoiser.toggle();
+            println!("Filtro de ruido: {}", denoiser.enabled);
+        }
+
+        if window.is_key_pressed(Key::Y, KeyRepeat::No) {
+            stereo.next_mode();
+            println!("Estéreo: {:?}", stereo.mode);
+        }
+
+        if window.is_key_pressed(Key::Home, KeyRepeat::Yes) {
+            stereo.scale_convergence(0.9);
+            println!("Convergencia: {:.2}", stereo.convergence);
+        }
+
+        if window.is_key_pressed(Key::End, KeyRepeat::Yes) {
+            stereo.scale_convergence(1.1);
+            println!("Convergencia: {:.2}", stereo.convergence);
+        }
+
+        if window.is_key_pressed(Key::Insert, KeyRepeat::Yes) {
+            stereo.scale_interpupillary_distance(1.1);
+            println!("Distancia interpupilar: {:.2}", stereo.interpupillary_distance);
+        }
+
+        if window.is_key_pressed(Key::Delete, KeyRepeat::Yes) {
+            stereo.scale_interpupillary_distance(0.9);
+            println!("Distancia interpupilar: {:.2}", stereo.interpupillary_distance);
+        }
+
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            settings.ambient.next_mode();
+            println!("Oclusión ambiental: {:?}", settings.ambient.mode);
+        }
+
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            settings.fog.enabled = !settings.fog.enabled;
+            println!("Niebla: {}", settings.fog.enabled);
+        }
+
+        if window.is_key_pressed(Key::LeftBracket, KeyRepeat::Yes) {
+            settings.fog.density = (settings.fog.density / 1.25).max(0.001);
+            println!("Densidad de la niebla: {:.4}", settings.fog.density);
+        }
+
+        if window.is_key_pressed(Key::RightBracket, KeyRepeat::Yes) {
+            settings.fog.density = (settings.fog.density * 1.25).min(0.2);
+            println!("Densidad de la niebla: {:.4}", settings.fog.density);
+        }
+
+        let scene = Scene {
+            objects: &objects,
+            lights: &lights,
+            previous_lights: &previous_lights,
+            time_of_day: &time_of_day,
+            weather: &weather,
+            clouds: &clouds,
+        };
+        let (width, height) = window.get_size();
+        if width > 0 && height > 0 && (width, height) != (framebuffer.width, framebuffer.height) {
+            framebuffer = Framebuffer::new(width, height);
+        }
+
+        let (render_width, render_height) = scaler.render_size(framebuffer.width, framebuffer.height);
+        if render_buffer.width != render_width || render_buffer.height != render_height {
+            render_buffer = Framebuffer::new(render_width, render_height);
+            aovs = AovBuffers::new(render_width, render_height);
+        }
+        let render_camera = scaler.limit_samples(&camera);
+
+        if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+            aov_view = aov_view.next();
+            println!("Pase: {}", aov_view.name());
+        }
+        let save_passes = window.is_key_pressed(Key::F7, KeyRepeat::No);
+        let save_float = window.is_key_pressed(Key::F8, KeyRepeat::No);
+        // Los pases sólo se llenan si se ven, se guardan o los usa el filtro; la reproyección
+        // temporal siempre los necesita
+        let fill_aovs = aov_view != AovKind::Beauty || denoiser.enabled || save_passes || save_float;
+
+        // Rayos primarios del cuadro: los píxeles trazados por sus muestras (el anaglifo traza dos imágenes)
+        let traced_pixels = if temporal.enabled() && stereo.mode == StereoMode::Off {
+            let size = (render_width, render_height);
+            temporal.render(&mut render_buffer, &render_camera, &mut aovs, |x, y, aov| {
+                aov.radiance = render_pixel(x, y, size, &scene, &render_camera, &settings, Some(aov));
+            });
+            temporal.traced
         } else {
-            (moon_position, Color::new(135, 206, 235), 0.5) 
+            temporal.reset();
+            let frame_aovs = if fill_aovs { Some(&mut aovs) } else { None };
+            render_view(&mut render_buffer, &scene, &render_camera, &settings, &stereo, frame_aovs);
+            let views = if stereo.mode == StereoMode::Anaglyph { 2 } else { 1 };
+            render_width * render_height * views
         };
+        let rays = traced_pixels * render_camera.samples_per_pixel() as usize;
+        if denoiser.enabled && stereo.mode == StereoMode::Off {
+            denoiser.apply(&aovs, &mut render_buffer);
+        }
 
-        let mut lights = vec![Light {
-            position: light_position,
-            color: light_color,
-            intensity: light_intensity,
-        }];
-
-        if sun_angle >= PI {
-            let glowstone_light = Light {
-                position: Vec3::new(7.0, 6.375, -7.125), 
-                color: Color::new(255, 223, 0),      
-                intensity: 0.1,                     
-            };
-            lights.push(glowstone_light);
+        if save_passes {
+            let saved = aovs.save_all("aovs").and_then(|_| render_buffer.save("aovs/beauty.png"));
+            match saved {
+                Ok(()) => println!("Pases guardados en aovs/"),
+                Err(error) => println!("No se pudieron guardar los pases: {}", error),
+            }
         }
 
-        if window.is_key_down(Key::Left) {
-            camera.orbit(rotation_speed, 0.0);
+        // Los pases sólo se llenan sin estéreo, así que la imagen en punto flotante también
+        if save_float {
+            if stereo.mode != StereoMode::Off {
+                println!("La imagen HDR sólo se guarda sin estéreo");
+            } else {
+                match aovs.save_exr("render.exr") {
+                    Ok(()) => println!("Imagen EXR guardada en render.exr"),
+                    Err(error) => println!("No se pudo guardar la imagen EXR: {}", error),
+                }
+                match aovs.save_hdr("render.hdr") {
+                    Ok(()) => println!("Imagen HDR guardada en render.hdr"),
+                    Err(error) => println!("No se pudo guardar la imagen HDR: {}", error),
+                }
+            }
         }
 
-        if window.is_key_down(Key::Right) {
-            camera.orbit(-rotation_speed, 0.0);
+        aovs.visualize(aov_view, &mut render_buffer);
+        upscale_bilinear(&render_buffer, &mut framebuffer);
+
+        if window.is_key_pressed(Key::F1, KeyRepeat::No) {
+            hud.toggle();
         }
 
-        if window.is_key_down(Key::Up) {
-            camera.orbit(0.0, -rotation_speed);
+        if window.is_key_pressed(Key::E, KeyRepeat::No) {
+            wireframe.toggle();
+            println!("Malla de cajas: {}", if wireframe.enabled { "activada" } else { "desactivada" });
+        }
+        // Con el estéreo la imagen no corresponde a una sola cámara, así que no se superpone la malla
+        let show_wireframe = wireframe.enabled && stereo.mode == StereoMode::Off;
+        let hovered = if show_wireframe {
+            object_under_cursor(&window, &camera, &objects, framebuffer.width, framebuffer.height)
+        } else {
+            None
+        };
+        if show_wireframe {
+            wireframe.draw(&mut framebuffer, &objects, &camera, hovered);
         }
 
-        if window.is_key_down(Key::Down) {
-            camera.orbit(0.0, rotation_speed);
+        let on_off = |enabled: bool| if enabled { "sí" } else { "no" };
+        let hud_lines = [
+            format!("Ojo {:.1} {:.1} {:.1}", camera.eye.x, camera.eye.y, camera.eye.z),
+            format!("Objetivo {:.1} {:.1} {:.1}", camera.center.x, camera.center.y, camera.center.z),
+            format!(
+                "Día {} {:02}:{:02}  x{}{}",
+                time_of_day.day() as i32 + 1,
+                time_of_day.hour() as u32,
+                (time_of_day.hour().fract() * 60.0) as u32,
+                time_of_day.speed,
+                if time_of_day.paused { " pausa" } else { "" },
+            ),
+            format!(
+                "Cámara {:?} {:?} FOV {:.0}°  estéreo {:?}",
+                camera_mode,
+                camera.projection,
+                camera.fov.to_degrees(),
+                stereo.mode,
+            ),
+            format!(
+                "Render {}x{}  objetos {}  muestras {}  reproyección 1/{}  filtro {}  pase {}",
+                render_width,
+                render_height,
+                objects.len(),
+                render_camera.samples_per_pixel(),
+                temporal.interval,
+                on_off(denoiser.enabled),
+                aov_view.name(),
+            ),
+            format!(
+                "AO {:?}  niebla {}  nubes {}  clima {:?}  depuración {}",
+                settings.ambient.mode,
+                on_off(settings.fog.enabled),
+                on_off(clouds.enabled),
+                weather.kind,
+                settings.debug.name(),
+            ),
+            match hovered.map(|index| (index, &objects[index])) {
+                Some((index, object)) => format!(
+                    "Cubo {}  min {:.2} {:.2} {:.2}  max {:.2} {:.2} {:.2}",
+                    index, object.min.x, object.min.y, object.min.z, object.max.x, object.max.y, object.max.z,
+                ),
+                None => format!("Malla {}", on_off(show_wireframe)),
+            },
+        ];
+        hud.draw(&mut framebuffer, &hud_lines);
+        hud.draw_minimap(&mut framebuffer, &objects, &camera);
+        if camera_mode == CameraMode::FreeFly || camera.autofocus {
+            hud.draw_crosshair(&mut framebuffer);
         }
-        if window.is_key_down(Key::X) {
-            camera.zoom(1.0);
+
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            match save_panorama("panorama.png", &scene, &camera, &settings) {
+                Ok(()) => println!("Panorama guardado en panorama.png"),
+                Err(error) => println!("No se pudo guardar el panorama: {}", error),
+            }
         }
 
-        if window.is_key_down(Key::Z) {
-            camera.zoom(-1.0);
+        if window.is_key_pressed(Key::F6, KeyRepeat::No) {
+            match save_cube_map("cubemap", &scene, &camera, &settings) {
+                Ok(()) => println!("Cube map guardado en cubemap/"),
+                Err(error) => println!("No se pudo guardar el cube map: {}", error),
+            }
         }
 
-        let is_night = sun_angle >= PI;
-        render(
-            &mut framebuffer,
-            &skybox,
-            &objects,
-            &camera,
-            &lights,
-            is_night,
-        );
+        previous_lights = lights;
 
         window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+            .update_with_buffer(&framebuffer.buffer, framebuffer.width, framebuffer.height)
             .unwrap();
 
-        std::thread::sleep(frame_delay);
+        if window.is_key_pressed(Key::F11, KeyRepeat::No) {
+            if !fullscreen {
+                windowed_size = window.get_size();
+            }
+            fullscreen = !fullscreen;
+            // En pantalla completa se parte del tamaño inicial para que la escala no se pase de la pantalla
+            let (width, height) = if fullscreen { (window_width, window_height) } else { windowed_size };
+            window = open_window(width, height, fullscreen);
+        }
+
+        // Sólo se espera lo que queda del presupuesto del cuadro
+        let frame_time = now.elapsed();
+        scaler.update(frame_time.as_secs_f32(), camera.samples_per_pixel());
+        hud.update(delta_seconds, frame_time.as_secs_f32(), rays);
+        if let Some(remaining) = frame_delay.checked_sub(frame_time) {
+            std::thread::sleep(remaining);
+        }
     }
 }
@@ -1,6 +1,6 @@
 use crate::color::Color;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Material {
     pub albedo: [f32; 2],
     pub specular: f32,
@@ -8,17 +8,24 @@ pub struct Material {
     pub reflectivity: f32,
     pub diffuse: Color,  
     pub fresnel_color: Color,
+    pub emission: f32,
 }
 
 impl Material {
-    pub fn new(albedo: [f32; 2], specular: f32, transparency: f32, reflectivity: f32, diffuse: Color, fresnel_color: Color) -> Material {
+    pub fn new(albedo: [f32; 2], specular: f32, transparency: f32, reflectivity: f32, diffuse: Color, fresnel_color: Color, emission: f32) -> Material {
         Material {
             albedo,
             specular,
             transparency,
             reflectivity,
             diffuse,
-            fresnel_color
+            fresnel_color,
+            emission
         }
     }
-}
\ No newline at end of file
+
+    // Los bloques transparentes (el agua) dejan pasar la luz: no proyectan sombras ni tapan la luz ambiental
+    pub fn casts_shadow(&self) -> bool {
+        self.transparency <= 0.0
+    }
+}
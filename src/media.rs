@@ -0,0 +1,161 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::{add_rgb, lerp_rgb};
+use crate::cube::Cube;
+use crate::light::{blocker_distance, Light};
+use crate::noise::hash_vec3;
+use crate::scene::Scene;
+
+// Distancia de niebla usada para los rayos que no chocan con nada
+pub const FOG_SKY_DISTANCE: f32 = 150.0;
+// Distancia máxima a lo largo del rayo en la que se muestrean los rayos de luz
+const SHAFT_DISTANCE: f32 = 60.0;
+// Anisotropía de Henyey-Greenstein: la niebla dispersa sobre todo hacia delante
+const SHAFT_ANISOTROPY: f32 = 0.6;
+const WATER_STEP_BIAS: f32 = 1e-3;
+
+// Niebla de altura exponencial con dispersión de la luz del sol y la luna
+#[derive(Debug, Clone, Copy)]
+pub struct HeightFog {
+    pub enabled: bool,
+    pub density: f32,
+    pub height_falloff: f32,
+    pub base_height: f32,
+    pub shaft_samples: u32,
+    pub shaft_strength: f32,
+}
+
+impl HeightFog {
+    pub fn new(density: f32, height_falloff: f32, base_height: f32, shaft_samples: u32, shaft_strength: f32) -> Self {
+        HeightFog {
+            enabled: true,
+            density,
+            height_falloff,
+            base_height,
+            shaft_samples,
+            shaft_strength,
+        }
+    }
+
+    fn density_at(&self, height: f32) -> f32 {
+        self.density * (-self.height_falloff * (height - self.base_height)).exp()
+    }
+
+    // Profundidad óptica integrada analíticamente a lo largo del rayo
+    fn optical_depth(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        let start = self.density_at(origin.y);
+        let slope = self.height_falloff * direction.y;
+        if slope.abs() < 1e-4 {
+            start * distance
+        } else {
+            start * (1.0 - (-slope * distance).exp()) / slope
+        }
+    }
+
+    pub fn transmittance(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        (-self.optical_depth(origin, direction, distance)).exp()
+    }
+
+    // Aplicar niebla y rayos de luz al color visto a `distance` unidades del origen
+    pub fn apply(&self, color: [f32; 3], origin: &Vec3, direction: &Vec3, distance: f32, scene: &Scene) -> [f32; 3] {
+        if !self.enabled || self.density <= 0.0 {
+            return color;
+        }
+
+        let transmittance = self.transmittance(origin, direction, distance);
+        let fog_color = scene.weather.overcast(scene.time_of_day.sky.horizon_color(direction));
+        let fogged = lerp_rgb(fog_color, color, transmittance);
+
+        if self.shaft_samples == 0 || self.shaft_strength <= 0.0 {
+            return fogged;
+        }
+
+        let shafts = self.light_shafts(origin, direction, distance, scene.lights, scene.objects);
+        add_rgb(fogged, shafts)
+    }
+
+    // Luz dispersada hacia la cámara, muestreada a lo largo del rayo contra las sombras
+    fn light_shafts(
+        &self,
+        origin: &Vec3,
+        direction: &Vec3,
+        distance: f32,
+        lights: &[Light],
+        objects: &[Cube],
+    ) -> [f32; 3] {
+        let march_distance = distance.min(SHAFT_DISTANCE);
+        let step = march_distance / self.shaft_samples as f32;
+        // Desplazamiento aleatorio por píxel para cambiar bandas por ruido
+        let jitter = hash_vec3(direction);
+
+        let mut scattered = [0.0; 3];
+        for i in 0..self.shaft_samples {
+            let t = (i as f32 + jitter) * step;
+            let point = origin + direction * t;
+            let local_density = self.density_at(point.y);
+            let transmittance = self.transmittance(origin, direction, t);
+
+            for light in lights {
+                // El sol y la luna llegan en paralelo; si se usara su posición, las muestras que
+                // pasan junto a ella brillarían como si la luz estuviera dentro de la niebla
+                let (light_dir, light_distance) = light.direction_from(&point);
+                if blocker_distance(&point, &light_dir, light_distance, objects).is_some() {
+                    continue;
+                }
+                let phase = henyey_greenstein(direction.dot(&light_dir), SHAFT_ANISOTROPY);
+                let amount = light.intensity * phase * local_density * step * transmittance * self.shaft_strength;
+                for (out, channel) in scattered.iter_mut().zip(light.color.to_f32().iter()) {
+                    *out += channel * amount;
+                }
+            }
+        }
+
+        scattered
+    }
+}
+
+// Función de fase normalizada para que la dispersión isotrópica valga 1
+pub fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / denominator.powf(1.5)
+}
+
+// Medio homogéneo que absorbe la luz dentro de los volúmenes de agua
+#[derive(Debug, Clone, Copy)]
+pub struct WaterMedium {
+    pub absorption: [f32; 3],
+}
+
+impl WaterMedium {
+    pub fn new(absorption: [f32; 3]) -> Self {
+        WaterMedium { absorption }
+    }
+
+    // Atenuar un color tras recorrer `distance` unidades de agua (Beer-Lambert)
+    pub fn absorb(&self, color: [f32; 3], distance: f32) -> [f32; 3] {
+        [0, 1, 2].map(|channel| color[channel] * (-self.absorption[channel] * distance).exp())
+    }
+
+    // Longitud recorrida dentro de bloques transparentes contiguos, hasta `max_distance`
+    pub fn path_length(&self, origin: &Vec3, direction: &Vec3, max_distance: f32, objects: &[Cube]) -> f32 {
+        let mut travelled = 0.0;
+        while travelled < max_distance {
+            let point = origin + direction * (travelled + WATER_STEP_BIAS);
+            let Some(volume) = objects
+                .iter()
+                .find(|object| object.material.transparency > 0.0 && object.contains(&point))
+            else {
+                break;
+            };
+            travelled += volume.exit_distance(&point, direction) + WATER_STEP_BIAS;
+        }
+        travelled.min(max_distance)
+    }
+
+    // Verificar si un punto está dentro de algún volumen de agua
+    pub fn contains(&self, point: &Vec3, objects: &[Cube]) -> bool {
+        objects
+            .iter()
+            .any(|object| object.material.transparency > 0.0 && object.contains(point))
+    }
+}
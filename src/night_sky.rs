@@ -0,0 +1,107 @@
+use nalgebra_glm::{rotate_vec3, Vec3};
+use std::f32::consts::PI;
+
+use crate::noise::{fbm, hash3};
+
+// Radio angular aparente de la luna
+const MOON_ANGULAR_RADIUS: f32 = 0.05;
+// Distancia a la que se coloca la luz de la luna para que ilumine desde donde se dibuja
+const MOON_LIGHT_DISTANCE: f32 = 1000.0;
+// Densidad de la rejilla de estrellas sobre la esfera celeste
+const STAR_GRID: f32 = 60.0;
+const STAR_PROBABILITY: f32 = 0.12;
+const STAR_RADIUS: f32 = 0.0035;
+// Días simulados en un ciclo lunar completo (como en Minecraft)
+pub const LUNAR_CYCLE_DAYS: f32 = 8.0;
+
+pub struct NightSky {
+    pub rotation: f32,
+    pub moon_direction: Vec3,
+    pub moon_phase: f32,
+}
+
+impl NightSky {
+    // `rotation` gira la bóveda de estrellas, `moon_phase` va de 0 (luna llena) a 1
+    pub fn new(rotation: f32, moon_position: Vec3, moon_phase: f32) -> Self {
+        NightSky {
+            rotation,
+            moon_direction: moon_position.normalize(),
+            moon_phase: moon_phase.rem_euclid(1.0),
+        }
+    }
+
+    // Fracción iluminada del disco lunar según la fase
+    pub fn moon_illumination(&self) -> f32 {
+        (1.0 + (2.0 * PI * self.moon_phase).cos()) * 0.5
+    }
+
+    pub fn moon_light_position(&self) -> Vec3 {
+        self.moon_direction * MOON_LIGHT_DISTANCE
+    }
+
+    // Brillo de las estrellas en una dirección, en [0, 1]
+    pub fn stars(&self, direction: &Vec3) -> f32 {
+        // Las estrellas giran con el cielo alrededor del mismo eje que el sol
+        let celestial = rotate_vec3(direction, -self.rotation, &Vec3::new(0.0, 0.0, 1.0));
+        let grid = celestial * STAR_GRID;
+        let cell = grid.map(|c| c.floor());
+        let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+
+        if hash3(x, y, z) > STAR_PROBABILITY {
+            return 0.0;
+        }
+
+        // Posición de la estrella dentro de su celda, lejos de los bordes
+        let jitter = Vec3::new(
+            0.25 + 0.5 * hash3(x + 17, y, z),
+            0.25 + 0.5 * hash3(x, y + 31, z),
+            0.25 + 0.5 * hash3(x, y, z + 47),
+        );
+        let star_direction = ((cell + jitter) / STAR_GRID).normalize();
+        let angle = celestial.dot(&star_direction).clamp(-1.0, 1.0).acos();
+        if angle > STAR_RADIUS {
+            return 0.0;
+        }
+
+        let brightness = 0.3 + 0.7 * hash3(x + 5, y + 9, z + 13);
+        brightness * (1.0 - angle / STAR_RADIUS)
+    }
+
+    // Color del disco lunar en una dirección, o None si la dirección no cae sobre la luna
+    pub fn moon(&self, direction: &Vec3) -> Option<[f32; 3]> {
+        let angle = direction.dot(&self.moon_direction).clamp(-1.0, 1.0).acos();
+        if angle > MOON_ANGULAR_RADIUS {
+            return None;
+        }
+
+        // Sistema local del disco: z apunta hacia el observador
+        let helper = if self.moon_direction.y.abs() > 0.99 {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            Vec3::new(0.0, 1.0, 0.0)
+        };
+        let right = self.moon_direction.cross(&helper).normalize();
+        let up = right.cross(&self.moon_direction);
+        let offset = direction - self.moon_direction;
+        let px = offset.dot(&right) / MOON_ANGULAR_RADIUS;
+        let py = offset.dot(&up) / MOON_ANGULAR_RADIUS;
+        let pz = (1.0 - px * px - py * py).max(0.0).sqrt();
+        let normal = Vec3::new(px, py, pz);
+
+        // Mares lunares a partir de ruido sobre la superficie de la esfera
+        let maria = fbm(&(normal * 3.0 + Vec3::new(11.0, 3.0, 7.0)), 4);
+        let albedo = 0.55 + 0.45 * ((maria - 0.35) / 0.3).clamp(0.0, 1.0);
+
+        // Terminador según la fase: el sol ilumina la luna desde un lado que gira
+        let phase_angle = 2.0 * PI * self.moon_phase;
+        let sun_on_moon = Vec3::new(phase_angle.sin(), 0.0, phase_angle.cos());
+        let lit = ((normal.dot(&sun_on_moon) + 0.05) / 0.1).clamp(0.0, 1.0);
+        let earthshine = 0.04;
+
+        // Borde suavizado del disco
+        let edge = ((1.0 - angle / MOON_ANGULAR_RADIUS) * 20.0).clamp(0.0, 1.0);
+        let intensity = albedo * (lit + earthshine) * edge;
+
+        Some([0.95 * intensity, 0.95 * intensity, 0.88 * intensity])
+    }
+}
@@ -0,0 +1,73 @@
+use nalgebra_glm::Vec3;
+
+// Hash entero de una celda 3D, devuelve un valor en [0, 1)
+pub fn hash3(x: i32, y: i32, z: i32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    (h & 0x00ff_ffff) as f32 / 16_777_216.0
+}
+
+// Hash de un punto a partir de los bits de sus coordenadas, en [0, 1)
+pub fn hash_vec3(point: &Vec3) -> f32 {
+    hash3(
+        point.x.to_bits() as i32,
+        point.y.to_bits() as i32,
+        point.z.to_bits() as i32,
+    )
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Ruido de valor 3D con interpolación suave, en [0, 1)
+pub fn value_noise(point: &Vec3) -> f32 {
+    let cell = point.map(|c| c.floor());
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+    let fx = fade(point.x - cell.x);
+    let fy = fade(point.y - cell.y);
+    let fz = fade(point.z - cell.z);
+
+    let c000 = hash3(x, y, z);
+    let c100 = hash3(x + 1, y, z);
+    let c010 = hash3(x, y + 1, z);
+    let c110 = hash3(x + 1, y + 1, z);
+    let c001 = hash3(x, y, z + 1);
+    let c101 = hash3(x + 1, y, z + 1);
+    let c011 = hash3(x, y + 1, z + 1);
+    let c111 = hash3(x + 1, y + 1, z + 1);
+
+    let x00 = lerp(c000, c100, fx);
+    let x10 = lerp(c010, c110, fx);
+    let x01 = lerp(c001, c101, fx);
+    let x11 = lerp(c011, c111, fx);
+
+    lerp(lerp(x00, x10, fy), lerp(x01, x11, fy), fz)
+}
+
+// Suma fractal de octavas de ruido, normalizada a [0, 1)
+pub fn fbm(point: &Vec3, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut total = 0.0;
+    let mut p = *point;
+
+    for _ in 0..octaves {
+        sum += value_noise(&p) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        p *= 2.03;
+    }
+
+    sum / total
+}
@@ -1,12 +1,26 @@
 use nalgebra_glm::Vec3;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use crate::color::Color;
 use crate::material::Material;  
 
+// Pruebas de intersección rayo-objeto hechas desde que arrancó el programa, para la vista de depuración
+static INTERSECTION_TESTS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn count_intersection_test() {
+    INTERSECTION_TESTS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn intersection_tests() -> usize {
+    INTERSECTION_TESTS.load(Ordering::Relaxed)
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Intersect {
     pub point: Vec3,
     pub normal: Vec3,
     pub distance: f32,
+    // Coordenadas de textura en la cara del objeto
+    pub uv: [f32; 2],
     pub is_intersecting: bool,
     pub material: Material,
 }
@@ -17,6 +31,7 @@ impl Intersect {
             point,
             normal,
             distance,
+            uv: [0.0, 0.0],
             is_intersecting: true,
             material,
         }
@@ -27,6 +42,7 @@ impl Intersect {
             point: Vec3::zeros(),
             normal: Vec3::zeros(),
             distance: 0.0,
+            uv: [0.0, 0.0],
             is_intersecting: false,
             material: Material {
                 albedo: [0.0, 0.0],
@@ -35,6 +51,7 @@ impl Intersect {
                 reflectivity: 0.0,
                 diffuse: Color::new(0, 0, 0),
                 fresnel_color: Color::new(0, 0, 0),
+                emission: 0.0,
             },
         }
     }
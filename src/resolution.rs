@@ -0,0 +1,119 @@
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+
+// Cuánto puede cambiar la escala en un solo cuadro, para que la imagen no parpadee
+const MAX_SCALE_STEP: f32 = 1.1;
+// Margen alrededor del presupuesto dentro del cual no se cambia nada
+const BUDGET_MARGIN: f32 = 0.1;
+
+// Ajusta la resolución interna (y las muestras por píxel) para que cada cuadro quepa en el presupuesto
+#[derive(Debug, Clone, Copy)]
+pub struct ResolutionScaler {
+    pub enabled: bool,
+    // Tiempo objetivo por cuadro en segundos
+    pub target_frame_time: f32,
+    // Fracción de la resolución de la ventana en cada eje
+    pub scale: f32,
+    pub min_scale: f32,
+    // Tope de muestras por píxel de la profundidad de campo y el desenfoque de movimiento
+    pub max_samples: u32,
+}
+
+impl ResolutionScaler {
+    pub fn new(target_frame_time: f32, min_scale: f32) -> Self {
+        ResolutionScaler {
+            enabled: false,
+            target_frame_time,
+            scale: 1.0,
+            min_scale,
+            max_samples: u32::MAX,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.scale = 1.0;
+        self.max_samples = u32::MAX;
+    }
+
+    // Ajustar la calidad según lo que tardó el último cuadro. El costo crece con el número de
+    // píxeles, así que la escala de cada eje sigue la raíz de la proporción de tiempos.
+    // Se bajan primero la resolución y después las muestras, y se recuperan en el orden inverso.
+    pub fn update(&mut self, frame_time: f32, samples: u32) {
+        if !self.enabled || frame_time <= 0.0 {
+            return;
+        }
+
+        let ratio = self.target_frame_time / frame_time;
+        if ratio < 1.0 - BUDGET_MARGIN {
+            if self.scale > self.min_scale {
+                self.scale = (self.scale * ratio.sqrt().max(1.0 / MAX_SCALE_STEP)).max(self.min_scale);
+            } else {
+                self.max_samples = (self.max_samples.min(samples) / 2).max(1);
+            }
+        } else if ratio > 1.0 + BUDGET_MARGIN {
+            if self.max_samples < samples {
+                self.max_samples *= 2;
+            } else {
+                self.scale = (self.scale * ratio.sqrt().min(MAX_SCALE_STEP)).min(1.0);
+            }
+        }
+    }
+
+    // Tamaño interno para una ventana de `width` x `height`
+    pub fn render_size(&self, width: usize, height: usize) -> (usize, usize) {
+        if !self.enabled {
+            return (width, height);
+        }
+        (
+            ((width as f32 * self.scale).round() as usize).max(1),
+            ((height as f32 * self.scale).round() as usize).max(1),
+        )
+    }
+
+    // Cámara con las muestras por píxel limitadas al tope actual
+    pub fn limit_samples(&self, camera: &Camera) -> Camera {
+        let mut camera = camera.clone();
+        if self.enabled {
+            camera.lens_samples = camera.lens_samples.min(self.max_samples);
+            camera.motion_samples = camera.motion_samples.min(self.max_samples);
+        }
+        camera
+    }
+}
+
+// Escalar `source` al tamaño de `target` con un filtro bilineal
+pub fn upscale_bilinear(source: &Framebuffer, target: &mut Framebuffer) {
+    if source.width == target.width && source.height == target.height {
+        target.buffer.copy_from_slice(&source.buffer);
+        return;
+    }
+
+    let scale_x = source.width as f32 / target.width as f32;
+    let scale_y = source.height as f32 / target.height as f32;
+    for y in 0..target.height {
+        // Se muestrea en el centro de cada píxel
+        let source_y = ((y as f32 + 0.5) * scale_y - 0.5).clamp(0.0, (source.height - 1) as f32);
+        let y0 = source_y.floor() as usize;
+        let y1 = (y0 + 1).min(source.height - 1);
+        let ty = source_y - y0 as f32;
+
+        for x in 0..target.width {
+            let source_x = ((x as f32 + 0.5) * scale_x - 0.5).clamp(0.0, (source.width - 1) as f32);
+            let x0 = source_x.floor() as usize;
+            let x1 = (x0 + 1).min(source.width - 1);
+            let tx = source_x - x0 as f32;
+
+            let pixel = |x: usize, y: usize| source.buffer[y * source.width + x];
+            let mut color = 0;
+            for shift in [16, 8, 0] {
+                let channel = |pixel: u32| ((pixel >> shift) & 0xFF) as f32;
+                let top = channel(pixel(x0, y0)) * (1.0 - tx) + channel(pixel(x1, y0)) * tx;
+                let bottom = channel(pixel(x0, y1)) * (1.0 - tx) + channel(pixel(x1, y1)) * tx;
+                let value = (top * (1.0 - ty) + bottom * ty).round() as u32;
+                color |= value.min(255) << shift;
+            }
+            target.draw_pixel(x, y, color);
+        }
+    }
+}
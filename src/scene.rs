@@ -0,0 +1,17 @@
+use crate::clouds::CloudLayer;
+use crate::cube::Cube;
+use crate::light::Light;
+use crate::time_of_day::TimeOfDay;
+use crate::weather::Weather;
+
+// Todo lo que el trazador de rayos necesita saber de la escena en un cuadro
+#[derive(Clone, Copy)]
+pub struct Scene<'a> {
+    pub objects: &'a [Cube],
+    pub lights: &'a [Light],
+    // Luces al comienzo del cuadro, para el desenfoque de movimiento
+    pub previous_lights: &'a [Light],
+    pub time_of_day: &'a TimeOfDay,
+    pub weather: &'a Weather,
+    pub clouds: &'a CloudLayer,
+}
@@ -0,0 +1,23 @@
+use crate::ambient::AmbientOcclusion;
+use crate::debug_view::DebugView;
+use crate::media::{HeightFog, WaterMedium};
+
+// Opciones de render que se pueden cambiar mientras corre la escena
+#[derive(Clone, Copy)]
+pub struct RenderSettings {
+    pub ambient: AmbientOcclusion,
+    pub fog: HeightFog,
+    pub water: WaterMedium,
+    pub debug: DebugView,
+}
+
+impl RenderSettings {
+    pub fn new(ambient: AmbientOcclusion, fog: HeightFog, water: WaterMedium) -> Self {
+        RenderSettings {
+            ambient,
+            fog,
+            water,
+            debug: DebugView::Off,
+        }
+    }
+}
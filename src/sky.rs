@@ -0,0 +1,240 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::{decode_gamma, Color};
+use crate::night_sky::NightSky;
+
+// Radio angular aparente del disco solar (exagerado para que se vea en la escena)
+const SUN_ANGULAR_RADIUS: f32 = 0.035;
+// Coeficientes de extinción por canal usados para teñir el sol cerca del horizonte
+const SUN_EXTINCTION: [f32; 3] = [0.02, 0.05, 0.12];
+const NIGHT_COLOR: [f32; 3] = [10.0 / 255.0, 10.0 / 255.0, 30.0 / 255.0];
+
+// Coeficientes de la función de distribución de Perez para una componente (Y, x o y)
+#[derive(Debug, Clone, Copy)]
+struct Perez {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+}
+
+impl Perez {
+    fn eval(&self, cos_theta: f32, gamma: f32, cos_gamma: f32) -> f32 {
+        (1.0 + self.a * (self.b / cos_theta).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+// Cielo analítico de Preetham, Shirley y Smits (1999)
+pub struct Sky {
+    pub sun_direction: Vec3,
+    pub turbidity: f32,
+    pub night: NightSky,
+    exposure: f32,
+    perez: [Perez; 3],
+    zenith: [f32; 3],
+    normalization: [f32; 3],
+    sun_color: [f32; 3],
+    daylight: f32,
+}
+
+impl Sky {
+    // `daylight` mezcla entre el cielo diurno (1) y el nocturno (0)
+    pub fn new(sun_direction: Vec3, turbidity: f32, daylight: f32, night: NightSky) -> Self {
+        let sun_direction = sun_direction.normalize();
+        let t = turbidity;
+
+        // El modelo sólo es válido con el sol sobre el horizonte
+        let sun_elevation = sun_direction.y.max(0.01);
+        let theta_s = sun_elevation.acos();
+
+        let perez = [
+            Perez {
+                a: 0.1787 * t - 1.4630,
+                b: -0.3554 * t + 0.4275,
+                c: -0.0227 * t + 5.3251,
+                d: 0.1206 * t - 2.5771,
+                e: -0.0670 * t + 0.3703,
+            },
+            Perez {
+                a: -0.0193 * t - 0.2592,
+                b: -0.0665 * t + 0.0008,
+                c: -0.0004 * t + 0.2125,
+                d: -0.0641 * t - 0.8989,
+                e: -0.0033 * t + 0.0452,
+            },
+            Perez {
+                a: -0.0167 * t - 0.2608,
+                b: -0.0950 * t + 0.0092,
+                c: -0.0079 * t + 0.2102,
+                d: -0.0441 * t - 1.6537,
+                e: -0.0109 * t + 0.0529,
+            },
+        ];
+
+        // Luminancia y cromaticidad en el cenit
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let theta2 = theta_s * theta_s;
+        let theta3 = theta2 * theta_s;
+        let t2 = t * t;
+        let zenith_x = t2 * (0.00166 * theta3 - 0.00375 * theta2 + 0.00209 * theta_s)
+            + t * (-0.02903 * theta3 + 0.06377 * theta2 - 0.03202 * theta_s + 0.00394)
+            + (0.11693 * theta3 - 0.21196 * theta2 + 0.06052 * theta_s + 0.25886);
+        let zenith_y = t2 * (0.00275 * theta3 - 0.00610 * theta2 + 0.00317 * theta_s)
+            + t * (-0.04214 * theta3 + 0.08970 * theta2 - 0.04153 * theta_s + 0.00516)
+            + (0.15346 * theta3 - 0.26756 * theta2 + 0.06670 * theta_s + 0.26688);
+
+        let zenith = [zenith_luminance.max(0.0), zenith_x, zenith_y];
+        let normalization = [
+            perez[0].eval(1.0, theta_s, theta_s.cos()),
+            perez[1].eval(1.0, theta_s, theta_s.cos()),
+            perez[2].eval(1.0, theta_s, theta_s.cos()),
+        ];
+
+        // Transmitancia de la atmósfera hacia el sol (masa de aire de Kasten-Young)
+        let zenith_degrees = theta_s.to_degrees().min(90.0);
+        let air_mass =
+            1.0 / (sun_elevation + 0.50572 * (96.07995 - zenith_degrees).powf(-1.6364));
+        let sun_color = [
+            (-SUN_EXTINCTION[0] * air_mass * t / 2.0).exp(),
+            (-SUN_EXTINCTION[1] * air_mass * t / 2.0).exp(),
+            (-SUN_EXTINCTION[2] * air_mass * t / 2.0).exp(),
+        ];
+
+        Sky {
+            sun_direction,
+            turbidity,
+            night,
+            exposure: 0.06,
+            perez,
+            zenith,
+            normalization,
+            sun_color,
+            daylight,
+        }
+    }
+
+    // Color de la luz solar directa, enrojecido según el ángulo del sol
+    pub fn sun_color(&self) -> Color {
+        Color::from_f32(self.sun_color[0], self.sun_color[1], self.sun_color[2])
+    }
+
+    // Radiancia del cielo en linear RGB (sin mapeo de tonos)
+    fn radiance(&self, direction: &Vec3) -> [f32; 3] {
+        let cos_theta = direction.y.max(0.001);
+        let cos_gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+
+        let luminance =
+            self.zenith[0] * self.perez[0].eval(cos_theta, gamma, cos_gamma) / self.normalization[0];
+        let x = self.zenith[1] * self.perez[1].eval(cos_theta, gamma, cos_gamma) / self.normalization[1];
+        let y = self.zenith[2] * self.perez[2].eval(cos_theta, gamma, cos_gamma) / self.normalization[2];
+
+        yxy_to_rgb(luminance.max(0.0), x, y)
+    }
+
+    // Radiancia dispersada por la atmósfera, incluido el resplandor del horizonte
+    fn scattered(&self, direction: &Vec3) -> [f32; 3] {
+        let mut rgb = self.radiance(direction);
+
+        // Resplandor en el horizonte del lado del sol, teñido con el color del sol
+        let horizon = 1.0 - direction.y.abs().min(1.0);
+        let towards_sun = direction.dot(&self.sun_direction).max(0.0);
+        let low_sun = 1.0 - self.sun_direction.y.abs().min(1.0);
+        let glow = horizon.powi(8) * towards_sun.powi(3) * low_sun.powi(2) * 4.0;
+        for (channel, sun) in rgb.iter_mut().zip(self.sun_color.iter()) {
+            *channel += glow * sun;
+        }
+
+        rgb
+    }
+
+    // Mapeo de tonos exponencial y mezcla con el cielo nocturno. El resultado queda en luz lineal,
+    // como el de las superficies; la corrección gamma se aplica al escribir el framebuffer
+    fn tone_map(&self, rgb: &[f32; 3]) -> [f32; 3] {
+        let night = decode_gamma(NIGHT_COLOR);
+        let mut mapped = [0.0; 3];
+        for ((out, channel), night) in mapped.iter_mut().zip(rgb.iter()).zip(night.iter()) {
+            let day = (1.0 - (-channel * self.exposure).exp()).max(0.0);
+            *out = night + (day - night) * self.daylight;
+        }
+        mapped
+    }
+
+    // Color del cielo cerca del horizonte en la dirección dada, sin sol ni estrellas (para la niebla)
+    pub fn horizon_color(&self, direction: &Vec3) -> [f32; 3] {
+        let horizontal = Vec3::new(direction.x, 0.0, direction.z);
+        let horizontal = if horizontal.magnitude() > 1e-4 {
+            horizontal.normalize()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        // Mirando hacia abajo, la niebla toma el cielo que tiene encima (la elevación reflejada); si se
+        // usara siempre el horizonte, el resplandor del sol se estiraría en una franja hasta el suelo
+        let elevation = (-direction.y).clamp(0.05, 1.0);
+        let spread = (1.0 - elevation * elevation).sqrt();
+        let sample = Vec3::new(horizontal.x * spread, elevation, horizontal.z * spread);
+        self.tone_map(&self.scattered(&sample))
+    }
+
+    pub fn color(&self, direction: &Vec3) -> [f32; 3] {
+        let direction = direction.normalize();
+        let mut rgb = self.scattered(&direction);
+
+        // Disco solar con oscurecimiento del limbo
+        let sun_angle = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        if sun_angle < SUN_ANGULAR_RADIUS {
+            let limb = 1.0 - (sun_angle / SUN_ANGULAR_RADIUS).powi(2);
+            let disk = 60.0 * (0.4 + 0.6 * limb.sqrt());
+            for (channel, sun) in rgb.iter_mut().zip(self.sun_color.iter()) {
+                *channel += disk * sun;
+            }
+        }
+
+        // Por debajo del horizonte se oscurece hacia el suelo
+        if direction.y < 0.0 {
+            let fade = (1.0 + direction.y * 4.0).max(0.2) * 0.6;
+            for channel in rgb.iter_mut() {
+                *channel *= fade;
+            }
+        }
+
+        let mut mapped = self.tone_map(&rgb);
+
+        // Luna y estrellas sobre el horizonte, que se desvanecen con la luz del día
+        let night_weight = 1.0 - self.daylight;
+        if direction.y > -0.02 {
+            if let Some(moon) = self.night.moon(&direction) {
+                // La luna y las estrellas vienen pensadas para la pantalla y se pasan a luz lineal
+                let visibility = 0.3 + 0.7 * night_weight;
+                let moon = decode_gamma(moon.map(|channel| channel * visibility));
+                for (out, channel) in mapped.iter_mut().zip(moon.iter()) {
+                    *out += channel;
+                }
+            } else if night_weight > 0.0 {
+                let horizon_fade = (direction.y * 10.0).clamp(0.0, 1.0);
+                let star = decode_gamma([self.night.stars(&direction) * night_weight * horizon_fade; 3]);
+                for (out, channel) in mapped.iter_mut().zip(star.iter()) {
+                    *out += channel;
+                }
+            }
+        }
+
+        mapped
+    }
+}
+
+fn yxy_to_rgb(luminance: f32, x: f32, y: f32) -> [f32; 3] {
+    let big_x = x * luminance / y;
+    let big_z = (1.0 - x - y) * luminance / y;
+
+    [
+        3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+    ]
+}
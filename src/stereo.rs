@@ -0,0 +1,82 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+
+const MIN_INTERPUPILLARY_DISTANCE: f32 = 0.05;
+const MIN_CONVERGENCE: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StereoMode {
+    Off,
+    // Ojo izquierdo en la mitad izquierda de la imagen y el derecho en la derecha
+    SideBySide,
+    // Rojo del ojo izquierdo y cian del derecho, para lentes rojo/cian
+    Anaglyph,
+}
+
+// Par de cámaras estéreo construido sobre la cámara principal
+#[derive(Debug, Clone, Copy)]
+pub struct StereoRig {
+    pub mode: StereoMode,
+    // Separación entre los ojos en unidades del mundo (un bloque mide 3.75)
+    pub interpupillary_distance: f32,
+    // Distancia al plano donde convergen los ojos; lo que está ahí aparece a la altura de la pantalla
+    pub convergence: f32,
+}
+
+impl StereoRig {
+    pub fn new(interpupillary_distance: f32, convergence: f32) -> Self {
+        StereoRig {
+            mode: StereoMode::Off,
+            interpupillary_distance,
+            convergence,
+        }
+    }
+
+    pub fn parse_mode(name: &str) -> Option<StereoMode> {
+        match name {
+            "off" => Some(StereoMode::Off),
+            "sbs" | "side-by-side" => Some(StereoMode::SideBySide),
+            "anaglyph" => Some(StereoMode::Anaglyph),
+            _ => None,
+        }
+    }
+
+    // Cambiar al siguiente modo: apagado -> lado a lado -> anaglifo
+    pub fn next_mode(&mut self) {
+        self.mode = match self.mode {
+            StereoMode::Off => StereoMode::SideBySide,
+            StereoMode::SideBySide => StereoMode::Anaglyph,
+            StereoMode::Anaglyph => StereoMode::Off,
+        };
+    }
+
+    pub fn scale_interpupillary_distance(&mut self, factor: f32) {
+        self.interpupillary_distance = (self.interpupillary_distance * factor).max(MIN_INTERPUPILLARY_DISTANCE);
+    }
+
+    pub fn scale_convergence(&mut self, factor: f32) {
+        self.convergence = (self.convergence * factor).max(MIN_CONVERGENCE);
+    }
+
+    // Cámaras (izquierda, derecha): separadas sobre el eje derecho y giradas hacia el punto de convergencia
+    pub fn eyes(&self, camera: &Camera) -> (Camera, Camera) {
+        (self.eye(camera, -0.5), self.eye(camera, 0.5))
+    }
+
+    // `side` es -0.5 para el ojo izquierdo y 0.5 para el derecho
+    fn eye(&self, camera: &Camera, side: f32) -> Camera {
+        let mut eye = camera.clone();
+        (eye.eye, eye.center) = self.toe_in(&camera.eye, &camera.center, &camera.up, side);
+        (eye.previous_eye, eye.previous_center) =
+            self.toe_in(&camera.previous_eye, &camera.previous_center, &camera.up, side);
+        eye
+    }
+
+    fn toe_in(&self, eye: &Vec3, center: &Vec3, up: &Vec3, side: f32) -> (Vec3, Vec3) {
+        let forward = (center - eye).normalize();
+        let right = forward.cross(up).normalize();
+        let target = eye + forward * self.convergence;
+        (eye + right * (self.interpupillary_distance * side), target)
+    }
+}
@@ -0,0 +1,213 @@
+use nalgebra_glm::Vec3;
+
+use crate::aov::{AovBuffers, AovSample};
+use crate::camera::{Camera, Projection};
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// El cielo no tiene profundidad; se reproyecta como si estuviera a esta distancia
+const SKY_DISTANCE: f32 = 1000.0;
+// Cuánto más lejos que sus vecinos recién trazados puede quedar un píxel de la historia
+const DEPTH_TOLERANCE: f32 = 0.1;
+// Cuadros que puede sobrevivir una muestra reutilizada antes de volver a trazarla
+const MAX_AGE: u8 = 4;
+
+// Reproyección temporal: en cada cuadro se traza sólo 1 de cada `interval` píxeles y el resto
+// se toma del cuadro anterior, reproyectado con su profundidad y la cámara de entonces
+pub struct TemporalCache {
+    // 1 desactiva la reproyección; 2 traza un tablero de ajedrez
+    pub interval: usize,
+    // Píxeles trazados en el último cuadro
+    pub traced: usize,
+    frame: usize,
+    previous_camera: Option<Camera>,
+    history: Option<AovBuffers>,
+    // Punto del mundo que ve cada píxel de la historia
+    positions: Vec<Vec3>,
+    // Cuadros desde que se trazó cada píxel de la historia
+    ages: Vec<u8>,
+}
+
+impl TemporalCache {
+    pub fn new(interval: usize) -> Self {
+        TemporalCache {
+            interval: interval.max(1),
+            traced: 0,
+            frame: 0,
+            previous_camera: None,
+            history: None,
+            positions: Vec::new(),
+            ages: Vec::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.interval > 1
+    }
+
+    // Cambiar entre apagado, 1 de cada 2 y 1 de cada 4 píxeles
+    pub fn next_interval(&mut self) {
+        self.interval = match self.interval {
+            1 => 2,
+            2 => 4,
+            _ => 1,
+        };
+        self.reset();
+    }
+
+    // Olvidar la historia, por ejemplo cuando el cuadro anterior se renderizó de otra forma
+    pub fn reset(&mut self) {
+        self.previous_camera = None;
+        self.history = None;
+    }
+
+    // Renderizar un cuadro llenando `aovs` y el framebuffer; `trace` traza un píxel y llena su muestra
+    pub fn render(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        camera: &Camera,
+        aovs: &mut AovBuffers,
+        mut trace: impl FnMut(usize, usize, &mut AovSample),
+    ) {
+        let width = framebuffer.width;
+        let height = framebuffer.height;
+
+        // Con varias muestras por píxel la profundidad no describe un único punto
+        let reusable = self.enabled()
+            && camera.aperture <= 0.0
+            && camera.shutter <= 0.0
+            && camera.projection != Projection::Equirectangular;
+        let reprojection = if reusable { self.reproject(camera, width, height) } else { None };
+
+        let slot = self.frame % self.interval;
+        let mut positions = vec![Vec3::zeros(); width * height];
+        let mut fresh = vec![false; width * height];
+        let mut ages = vec![0; width * height];
+        self.traced = 0;
+
+        let mut trace_pixel = |x: usize, y: usize, aovs: &mut AovBuffers, positions: &mut [Vec3]| {
+            let mut sample = AovSample::empty();
+            trace(x, y, &mut sample);
+            aovs.set(x, y, &sample);
+            positions[y * width + x] = world_position(camera, x, y, width, height, sample.depth);
+        };
+
+        // Primero los píxeles que tocan en este cuadro (todos si no hay historia utilizable)
+        for y in 0..height {
+            for x in 0..width {
+                if reprojection.is_none() || (x + 3 * y) % self.interval == slot {
+                    trace_pixel(x, y, aovs, &mut positions);
+                    fresh[y * width + x] = true;
+                    self.traced += 1;
+                }
+            }
+        }
+
+        // El resto sale de la historia, salvo donde aparece algo que el cuadro anterior no veía
+        if let (Some(reprojection), Some(history)) = (&reprojection, &self.history) {
+            for y in 0..height {
+                for x in 0..width {
+                    let index = y * width + x;
+                    if fresh[index] {
+                        continue;
+                    }
+
+                    match reprojection[index] {
+                        Some((source, depth))
+                            if self.ages[source] + 1 < MAX_AGE && !occluded(aovs, &fresh, x, y, depth) =>
+                        {
+                            let mut sample = history.get(source % width, source / width);
+                            if sample.depth.is_finite() {
+                                sample.depth = depth;
+                            }
+                            aovs.set(x, y, &sample);
+                            positions[index] = self.positions[source];
+                            ages[index] = self.ages[source] + 1;
+                        }
+                        _ => {
+                            trace_pixel(x, y, aovs, &mut positions);
+                            self.traced += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                framebuffer.draw_pixel(x, y, Color::from_linear(aovs.radiance[y * width + x]).to_hex());
+            }
+        }
+
+        if reusable {
+            self.history = Some(aovs.clone());
+            self.positions = positions;
+            self.ages = ages;
+            self.previous_camera = Some(camera.clone());
+        } else {
+            self.reset();
+        }
+        self.frame += 1;
+    }
+
+    // Proyectar cada punto de la historia en la cámara actual; para cada píxel guarda el índice del
+    // píxel de origen más cercano y su nueva profundidad lineal
+    fn reproject(&self, camera: &Camera, width: usize, height: usize) -> Option<Vec<Option<(usize, f32)>>> {
+        let history = self.history.as_ref()?;
+        let previous_camera = self.previous_camera.as_ref()?;
+        if history.width != width || history.height != height || previous_camera.projection != camera.projection {
+            return None;
+        }
+
+        let aspect_ratio = width as f32 / height as f32;
+        let mut reprojection: Vec<Option<(usize, f32)>> = vec![None; width * height];
+        for (source, position) in self.positions.iter().enumerate() {
+            let Some((screen_x, screen_y, depth)) = camera.project(position, aspect_ratio) else {
+                continue;
+            };
+            let x = ((screen_x + 1.0) * width as f32 / 2.0).round();
+            let y = ((1.0 - screen_y) * height as f32 / 2.0).round();
+            if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+                continue;
+            }
+
+            let target = &mut reprojection[y as usize * width + x as usize];
+            if target.is_none_or(|(_, closest)| depth < closest) {
+                *target = Some((source, depth));
+            }
+        }
+        Some(reprojection)
+    }
+}
+
+// Punto del mundo que ve el píxel según su profundidad lineal
+fn world_position(camera: &Camera, x: usize, y: usize, width: usize, height: usize, depth: f32) -> Vec3 {
+    let screen_x = (2.0 * x as f32) / width as f32 - 1.0;
+    let screen_y = -(2.0 * y as f32) / height as f32 + 1.0;
+    let (origin, direction) = camera.ray(screen_x, screen_y, width as f32 / height as f32, (0.5, 0.5));
+    if depth.is_finite() {
+        let forward = (camera.center - camera.eye).normalize();
+        origin + direction * (depth / direction.dot(&forward))
+    } else {
+        origin + direction * SKY_DISTANCE
+    }
+}
+
+// Un píxel de la historia que queda detrás de todos sus vecinos recién trazados estaba tapado
+// por algo que ahora se ve (o se coló entre los huecos de un objeto cercano)
+fn occluded(aovs: &AovBuffers, fresh: &[bool], x: usize, y: usize, depth: f32) -> bool {
+    let mut neighbors = 0;
+    for ny in y.saturating_sub(1)..=(y + 1).min(aovs.height - 1) {
+        for nx in x.saturating_sub(1)..=(x + 1).min(aovs.width - 1) {
+            let index = ny * aovs.width + nx;
+            if !fresh[index] {
+                continue;
+            }
+            if depth <= aovs.depth[index] * (1.0 + DEPTH_TOLERANCE) {
+                return false;
+            }
+            neighbors += 1;
+        }
+    }
+    neighbors > 0
+}
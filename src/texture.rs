@@ -13,11 +13,20 @@ impl Texture {
 
     // Devuelve el color de la textura en coordenadas UV
     pub fn get_color(&self, u: f32, v: f32) -> Color {
+        let pixel = self.texel(u, v);
+
+        Color::new(pixel[0], pixel[1], pixel[2])  
+    }
+
+    // Opacidad de la textura en coordenadas UV, en [0, 1]
+    pub fn get_alpha(&self, u: f32, v: f32) -> f32 {
+        self.texel(u, v)[3] as f32 / 255.0
+    }
+
+    fn texel(&self, u: f32, v: f32) -> image::Rgba<u8> {
         let (width, height) = self.image.dimensions();
         let x = (u * width as f32) as u32 % width;
         let y = (v * height as f32) as u32 % height;
-        let pixel = self.image.get_pixel(x, y);
-
-        Color::new(pixel[0], pixel[1], pixel[2])  
+        self.image.get_pixel(x, y)
     }
 }
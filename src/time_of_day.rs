@@ -0,0 +1,186 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::light::{Light, LightKind};
+use crate::night_sky::{NightSky, LUNAR_CYCLE_DAYS};
+use crate::sky::Sky;
+
+// Seno de la elevación del sol al terminar el crepúsculo civil (-6°)
+const CIVIL_TWILIGHT: f32 = -0.1045;
+const TURBIDITY: f32 = 2.5;
+const MOON_COLOR: Color = Color::new(135, 206, 235);
+const SUN_INTENSITY: f32 = 1.0;
+const MOON_INTENSITY: f32 = 0.5;
+
+// Velocidad con la que se adelanta o retrocede el reloj al mantener las teclas, en días por segundo
+const SCRUB_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 64.0;
+
+// Hora y estado del ciclo día/noche. `elapsed` se mide en días simulados y
+// el día empieza con el amanecer, a las 6:00. El reloj avanza con el tiempo
+// real: un día dura `day_length` segundos divididos entre `speed`.
+pub struct TimeOfDay {
+    elapsed: f32,
+    pub day_length: f32,
+    pub speed: f32,
+    pub paused: bool,
+    pub sky: Sky,
+}
+
+impl TimeOfDay {
+    pub fn new(elapsed: f32, day_length: f32) -> Self {
+        TimeOfDay {
+            elapsed,
+            day_length,
+            speed: 1.0,
+            paused: false,
+            sky: build_sky(elapsed),
+        }
+    }
+
+    // Avanzar el reloj según los segundos reales transcurridos desde el último cuadro
+    pub fn update(&mut self, delta_seconds: f32) {
+        if !self.paused {
+            self.advance(delta_seconds * self.speed / self.day_length);
+        }
+    }
+
+    pub fn advance(&mut self, days: f32) {
+        self.set_elapsed(self.elapsed + days);
+    }
+
+    // Desplazar el reloj manualmente (hacia atrás con `direction` negativo), aunque esté en pausa
+    pub fn scrub(&mut self, delta_seconds: f32, direction: f32) {
+        self.advance(direction * SCRUB_SPEED * delta_seconds);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn speed_up(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.speed = (self.speed / 2.0).max(1.0 / MAX_SPEED);
+    }
+
+    // Saltar a una hora del reloj dentro del día actual
+    pub fn jump_to_hour(&mut self, hour: f32) {
+        let fraction = ((hour - 6.0) / 24.0).rem_euclid(1.0);
+        self.set_elapsed(self.day() + fraction);
+    }
+
+    pub fn set_elapsed(&mut self, elapsed: f32) {
+        self.elapsed = elapsed;
+        self.sky = build_sky(elapsed);
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    // Número de día simulado desde el inicio
+    pub fn day(&self) -> f32 {
+        self.elapsed.floor()
+    }
+
+    // Hora del reloj en [0, 24)
+    pub fn hour(&self) -> f32 {
+        (self.elapsed.rem_euclid(1.0) * 24.0 + 6.0) % 24.0
+    }
+
+    pub fn sun_angle(&self) -> f32 {
+        sun_angle(self.elapsed)
+    }
+
+    pub fn sun_position(&self) -> Vec3 {
+        orbit_position(self.sun_angle())
+    }
+
+    // Seno de la elevación del sol sobre el horizonte
+    pub fn sun_elevation(&self) -> f32 {
+        self.sky.sun_direction.y
+    }
+
+    pub fn moon_elevation(&self) -> f32 {
+        self.sky.night.moon_direction.y
+    }
+
+    // 1 de día, 0 de noche; la transición dura el crepúsculo civil
+    pub fn daylight(&self) -> f32 {
+        daylight(self.sun_elevation())
+    }
+
+    // Intensidad de la luz solar directa, que se apaga al ponerse el sol
+    pub fn sun_intensity(&self) -> f32 {
+        SUN_INTENSITY * smoothstep(-0.02, 0.2, self.sun_elevation())
+    }
+
+    pub fn moon_intensity(&self) -> f32 {
+        let phase = 0.2 + 0.8 * self.sky.night.moon_illumination();
+        MOON_INTENSITY * phase * smoothstep(-0.02, 0.2, self.moon_elevation()) * (1.0 - self.daylight())
+    }
+
+    // Factor de la luz ambiental: el cielo nocturno ilumina menos
+    pub fn ambient_factor(&self) -> f32 {
+        0.3 + 0.7 * self.daylight()
+    }
+
+    // Intensidad de los materiales emisivos, que se encienden al oscurecer
+    pub fn emission(&self) -> f32 {
+        1.0 - self.daylight()
+    }
+
+    // Luces del sol y la luna para el instante actual (sólo las que iluminan)
+    pub fn lights(&self) -> Vec<Light> {
+        let mut lights = Vec::new();
+
+        let sun_intensity = self.sun_intensity();
+        if sun_intensity > 0.0 {
+            lights.push(Light::new(self.sun_position(), self.sky.sun_color(), sun_intensity, LightKind::Sun));
+        }
+
+        let moon_intensity = self.moon_intensity();
+        if moon_intensity > 0.0 {
+            lights.push(Light::new(
+                self.sky.night.moon_light_position(),
+                MOON_COLOR,
+                moon_intensity,
+                LightKind::Moon,
+            ));
+        }
+
+        lights
+    }
+}
+
+fn sun_angle(elapsed: f32) -> f32 {
+    elapsed.rem_euclid(1.0) * 2.0 * PI
+}
+
+fn orbit_position(angle: f32) -> Vec3 {
+    Vec3::new(15.0 * angle.cos(), 25.0 * angle.sin(), 15.0)
+}
+
+fn build_sky(elapsed: f32) -> Sky {
+    let angle = sun_angle(elapsed);
+    let sun_position = orbit_position(angle);
+
+    // Las fases de la luna avanzan una vez por día simulado
+    let moon_phase = elapsed.floor() / LUNAR_CYCLE_DAYS;
+    let night = NightSky::new(angle, orbit_position(angle + PI), moon_phase);
+
+    Sky::new(sun_position, TURBIDITY, daylight(sun_position.normalize().y), night)
+}
+
+fn daylight(sun_elevation: f32) -> f32 {
+    smoothstep(CIVIL_TWILIGHT, 0.05, sun_elevation)
+}
+
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,287 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::{lerp_rgb, Color};
+use crate::noise::hash3;
+
+// Volumen alrededor del diorama en el que viven las partículas
+const AREA_MIN: Vec3 = Vec3::new(-14.0, -6.0, -14.0);
+const AREA_MAX: Vec3 = Vec3::new(14.0, 18.0, 14.0);
+const MAX_RAIN: usize = 400;
+const MAX_SNOW: usize = 500;
+const MAX_PETALS: usize = 40;
+// Segundos que tarda el cielo en nublarse o despejarse por completo
+const TRANSITION_SECONDS: f32 = 4.0;
+const WIND: Vec3 = Vec3::new(1.2, 0.0, 0.4);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Snow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParticleKind {
+    Rain,
+    Snow,
+    Petal,
+}
+
+impl ParticleKind {
+    fn radius(&self) -> f32 {
+        match self {
+            ParticleKind::Rain => 0.04,
+            ParticleKind::Snow => 0.1,
+            ParticleKind::Petal => 0.1,
+        }
+    }
+
+    // Longitud del trazo que deja la partícula (las gotas se ven como líneas)
+    fn streak(&self) -> f32 {
+        match self {
+            ParticleKind::Rain => 0.8,
+            _ => 0.0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            ParticleKind::Rain => Color::new(170, 190, 215),
+            ParticleKind::Snow => Color::new(245, 248, 255),
+            ParticleKind::Petal => Color::new(255, 170, 200),
+        }
+    }
+
+    fn opacity(&self) -> f32 {
+        match self {
+            ParticleKind::Rain => 0.45,
+            ParticleKind::Snow => 0.9,
+            ParticleKind::Petal => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub kind: ParticleKind,
+    // Desfase para que cada partícula oscile a su propio ritmo
+    pub phase: f32,
+    // Trazo desde la cabeza hasta la cola, calculado al mover la partícula
+    tail: Vec3,
+}
+
+pub struct ParticleHit {
+    pub distance: f32,
+    pub color: Color,
+    pub opacity: f32,
+}
+
+pub struct Weather {
+    pub kind: WeatherKind,
+    pub particles: Vec<Particle>,
+    // Cobertura del cielo en [0, 1]; sigue a `kind` con una transición suave
+    pub coverage: f32,
+    // Regiones (mín, máx) desde las que caen los pétalos, normalmente las copas de los árboles
+    petal_emitters: Vec<(Vec3, Vec3)>,
+    time: f32,
+    seed: i32,
+}
+
+impl Weather {
+    pub fn new(kind: WeatherKind, petal_emitters: Vec<(Vec3, Vec3)>) -> Self {
+        Weather {
+            kind,
+            particles: Vec::new(),
+            coverage: if kind == WeatherKind::Clear { 0.0 } else { 1.0 },
+            petal_emitters,
+            time: 0.0,
+            seed: 0,
+        }
+    }
+
+    // Cambiar al siguiente clima: despejado -> lluvia -> nieve
+    pub fn next_kind(&mut self) {
+        self.kind = match self.kind {
+            WeatherKind::Clear => WeatherKind::Rain,
+            WeatherKind::Rain => WeatherKind::Snow,
+            WeatherKind::Snow => WeatherKind::Clear,
+        };
+    }
+
+    fn random(&mut self) -> f32 {
+        self.seed = self.seed.wrapping_add(1);
+        hash3(self.seed, 7919, 104_729)
+    }
+
+    fn random_in(&mut self, min: &Vec3, max: &Vec3) -> Vec3 {
+        Vec3::new(
+            min.x + (max.x - min.x) * self.random(),
+            min.y + (max.y - min.y) * self.random(),
+            min.z + (max.z - min.z) * self.random(),
+        )
+    }
+
+    fn spawn(&mut self, kind: ParticleKind, anywhere: bool) -> Particle {
+        let position = match kind {
+            ParticleKind::Petal if !self.petal_emitters.is_empty() => {
+                let index = (self.random() * self.petal_emitters.len() as f32) as usize;
+                let (min, max) = self.petal_emitters[index.min(self.petal_emitters.len() - 1)];
+                self.random_in(&min, &max)
+            }
+            _ => {
+                // Las partículas nuevas aparecen arriba, salvo al llenar el volumen por primera vez
+                let mut position = self.random_in(&AREA_MIN, &AREA_MAX);
+                if !anywhere {
+                    position.y = AREA_MAX.y;
+                }
+                position
+            }
+        };
+
+        let velocity = match kind {
+            ParticleKind::Rain => Vec3::new(0.0, -18.0 - 4.0 * self.random(), 0.0) + WIND * 0.5,
+            ParticleKind::Snow => Vec3::new(0.0, -1.2 - 0.6 * self.random(), 0.0) + WIND * 0.6,
+            ParticleKind::Petal => Vec3::new(0.0, -0.7 - 0.4 * self.random(), 0.0) + WIND,
+        };
+
+        Particle {
+            position,
+            velocity,
+            kind,
+            phase: self.random() * std::f32::consts::TAU,
+            tail: -velocity.normalize() * kind.streak(),
+        }
+    }
+
+    fn target_count(&self, kind: ParticleKind) -> usize {
+        match kind {
+            ParticleKind::Rain if self.kind == WeatherKind::Rain => (MAX_RAIN as f32 * self.coverage) as usize,
+            ParticleKind::Snow if self.kind == WeatherKind::Snow => (MAX_SNOW as f32 * self.coverage) as usize,
+            ParticleKind::Petal => MAX_PETALS,
+            _ => 0,
+        }
+    }
+
+    // Simular las partículas durante `delta_seconds` segundos
+    pub fn update(&mut self, delta_seconds: f32) {
+        self.time += delta_seconds;
+
+        let target = if self.kind == WeatherKind::Clear { 0.0 } else { 1.0 };
+        let step = delta_seconds / TRANSITION_SECONDS;
+        self.coverage += (target - self.coverage).clamp(-step, step);
+
+        let time = self.time;
+        for particle in self.particles.iter_mut() {
+            let mut velocity = particle.velocity;
+            // Los copos y pétalos se mecen con el viento
+            if particle.kind != ParticleKind::Rain {
+                let sway = (time * 1.7 + particle.phase).sin();
+                velocity += Vec3::new(sway * 0.8, 0.0, (time * 1.3 + particle.phase).cos() * 0.8);
+            }
+            particle.position += velocity * delta_seconds;
+        }
+
+        // Quitar las que salieron del volumen y las que sobran para el clima actual
+        let mut counts = [0usize; 3];
+        let targets = [
+            self.target_count(ParticleKind::Rain),
+            self.target_count(ParticleKind::Snow),
+            self.target_count(ParticleKind::Petal),
+        ];
+        self.particles.retain(|particle| {
+            let inside = particle.position.y > AREA_MIN.y
+                && particle.position.x > AREA_MIN.x
+                && particle.position.x < AREA_MAX.x
+                && particle.position.z > AREA_MIN.z
+                && particle.position.z < AREA_MAX.z;
+            let index = particle.kind as usize;
+            if inside && counts[index] < targets[index] {
+                counts[index] += 1;
+                true
+            } else {
+                false
+            }
+        });
+
+        // Reponer hasta llegar a la cantidad deseada de cada tipo
+        let first_fill = self.particles.is_empty();
+        for (index, kind) in [ParticleKind::Rain, ParticleKind::Snow, ParticleKind::Petal].into_iter().enumerate() {
+            for _ in counts[index]..targets[index] {
+                let particle = self.spawn(kind, first_fill);
+                self.particles.push(particle);
+            }
+        }
+    }
+
+    // Factor que atenúa la luz del sol y la luna con el cielo cubierto
+    pub fn light_factor(&self) -> f32 {
+        1.0 - 0.6 * self.coverage
+    }
+
+    // Oscurecer y desaturar un color del cielo según la cobertura de nubes
+    pub fn overcast(&self, color: [f32; 3]) -> [f32; 3] {
+        if self.coverage <= 0.0 {
+            return color;
+        }
+        let grey = (0.3 * color[0] + 0.59 * color[1] + 0.11 * color[2]) * 0.55;
+        lerp_rgb(color, [grey; 3], self.coverage * 0.85)
+    }
+
+    // Partícula más cercana que cruza el rayo antes de `max_distance`
+    pub fn intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32) -> Option<ParticleHit> {
+        let mut closest: Option<ParticleHit> = None;
+        let mut zbuffer = max_distance;
+
+        for particle in &self.particles {
+            let radius = particle.kind.radius();
+            let tail = particle.tail;
+
+            // Descarte rápido con la esfera que envuelve el trazo
+            let bound = particle.kind.streak() * 0.5 + radius;
+            let to_center = particle.position + tail * 0.5 - ray_origin;
+            let along = to_center.dot(ray_direction);
+            if along < 0.0 || along - bound > zbuffer {
+                continue;
+            }
+            if to_center.magnitude_squared() - along * along > bound * bound {
+                continue;
+            }
+
+            // Punto del segmento (cabeza -> cola) más cercano al rayo
+            let w = ray_origin - particle.position;
+            let b = ray_direction.dot(&tail);
+            let c = tail.magnitude_squared();
+            let d = ray_direction.dot(&w);
+            let e = tail.dot(&w);
+            let s = if c > 1e-6 {
+                let denominator = c - b * b;
+                if denominator.abs() > 1e-6 {
+                    ((e - b * d) / denominator).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                }
+            } else {
+                0.0
+            };
+            let t = b * s - d;
+            if t < 0.0 || t > zbuffer {
+                continue;
+            }
+
+            let on_ray = ray_origin + ray_direction * t;
+            let on_segment = particle.position + tail * s;
+            if (on_ray - on_segment).magnitude_squared() < radius * radius {
+                zbuffer = t;
+                closest = Some(ParticleHit {
+                    distance: t,
+                    color: particle.kind.color(),
+                    opacity: particle.kind.opacity(),
+                });
+            }
+        }
+
+        closest
+    }
+}
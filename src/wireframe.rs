@@ -0,0 +1,138 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+
+const EDGE_COLOR: u32 = 0x00FFFF;
+const HIGHLIGHT_COLOR: u32 = 0xFFFF00;
+// Distancia mínima delante de la cámara; las aristas que la cruzan se recortan ahí
+const NEAR_DISTANCE: f32 = 0.05;
+
+// Índices de las esquinas (bit 0 = x, bit 1 = y, bit 2 = z en el máximo) unidas por cada arista
+const EDGES: [(usize, usize); 12] = [
+    (0, 1), (2, 3), (4, 5), (6, 7),
+    (0, 2), (1, 3), (4, 6), (5, 7),
+    (0, 4), (1, 5), (2, 6), (3, 7),
+];
+
+// Superposición con las aristas de la caja de cada cubo proyectadas con la cámara. La escena no
+// tiene estructura de aceleración (cada rayo prueba todos los cubos), así que sólo se dibujan los cubos.
+#[derive(Debug, Clone, Copy)]
+pub struct WireframeOverlay {
+    pub enabled: bool,
+}
+
+impl WireframeOverlay {
+    pub fn new() -> Self {
+        WireframeOverlay { enabled: false }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    // Dibujar todas las cajas y, encima y en otro color, la del cubo `highlighted`
+    pub fn draw(&self, framebuffer: &mut Framebuffer, objects: &[Cube], camera: &Camera, highlighted: Option<usize>) {
+        if !self.enabled {
+            return;
+        }
+
+        framebuffer.set_current_color(EDGE_COLOR);
+        for (index, object) in objects.iter().enumerate() {
+            if Some(index) != highlighted {
+                draw_box(framebuffer, object, camera);
+            }
+        }
+
+        if let Some(object) = highlighted.and_then(|index| objects.get(index)) {
+            framebuffer.set_current_color(HIGHLIGHT_COLOR);
+            draw_box(framebuffer, object, camera);
+        }
+    }
+}
+
+fn draw_box(framebuffer: &mut Framebuffer, object: &Cube, camera: &Camera) {
+    let corners: [Vec3; 8] = std::array::from_fn(|corner| {
+        Vec3::new(
+            if corner & 1 == 0 { object.min.x } else { object.max.x },
+            if corner & 2 == 0 { object.min.y } else { object.max.y },
+            if corner & 4 == 0 { object.min.z } else { object.max.z },
+        )
+    });
+
+    for (start, end) in EDGES {
+        draw_edge(framebuffer, camera, corners[start], corners[end]);
+    }
+}
+
+// Proyectar una arista, recortando la parte que queda detrás de la cámara
+fn draw_edge(framebuffer: &mut Framebuffer, camera: &Camera, mut start: Vec3, mut end: Vec3) {
+    let forward = (camera.center - camera.eye).normalize();
+    let start_depth = (start - camera.eye).dot(&forward);
+    let end_depth = (end - camera.eye).dot(&forward);
+    if start_depth < NEAR_DISTANCE && end_depth < NEAR_DISTANCE {
+        return;
+    }
+    if start_depth < NEAR_DISTANCE {
+        start += (end - start) * ((NEAR_DISTANCE - start_depth) / (end_depth - start_depth));
+    } else if end_depth < NEAR_DISTANCE {
+        end += (start - end) * ((NEAR_DISTANCE - end_depth) / (start_depth - end_depth));
+    }
+
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+    let to_screen = |point: &Vec3| {
+        let (screen_x, screen_y, _) = camera.project(point, width / height)?;
+        Some(((screen_x + 1.0) * width / 2.0, (1.0 - screen_y) * height / 2.0))
+    };
+
+    let (Some(start), Some(end)) = (to_screen(&start), to_screen(&end)) else {
+        return;
+    };
+    // Sólo se recorre la parte del segmento que cae dentro de la pantalla
+    if let Some(((x0, y0), (x1, y1))) = clip_segment(start, end, width - 1.0, height - 1.0) {
+        framebuffer.line(
+            x0.round() as isize,
+            y0.round() as isize,
+            x1.round() as isize,
+            y1.round() as isize,
+        );
+    }
+}
+
+// Recortar un segmento al rectángulo [0, max_x] x [0, max_y] con el algoritmo de Liang–Barsky
+fn clip_segment(start: (f32, f32), end: (f32, f32), max_x: f32, max_y: f32) -> Option<((f32, f32), (f32, f32))> {
+    let delta_x = end.0 - start.0;
+    let delta_y = end.1 - start.1;
+    let mut enter: f32 = 0.0;
+    let mut exit: f32 = 1.0;
+
+    // Cada borde como (p, q): el segmento está del lado de dentro mientras p * t <= q
+    let edges = [
+        (-delta_x, start.0),
+        (delta_x, max_x - start.0),
+        (-delta_y, start.1),
+        (delta_y, max_y - start.1),
+    ];
+    for (p, q) in edges {
+        if p == 0.0 {
+            // Paralelo al borde: o está todo dentro o todo fuera
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            enter = enter.max(q / p);
+        } else {
+            exit = exit.min(q / p);
+        }
+        if enter > exit {
+            return None;
+        }
+    }
+
+    Some((
+        (start.0 + delta_x * enter, start.1 + delta_y * enter),
+        (start.0 + delta_x * exit, start.1 + delta_y * exit),
+    ))
+}